@@ -0,0 +1,17 @@
+use crate::monitor_dir;
+use std::path::{Path, PathBuf};
+
+pub const SECRET_HEADER: &str = "x-ccm-secret";
+pub const SECRET_FILE: &str = "secret";
+
+/// 与 ccm-hook 放在同一目录
+pub fn secret_path() -> Option<PathBuf> {
+    monitor_dir().map(|d| d.join("bin").join(SECRET_FILE))
+}
+
+pub fn read_secret(path: &Path) -> Option<String> {
+    std::fs::read_to_string(path)
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
@@ -0,0 +1,155 @@
+//! `ccm-hook`：由 Claude Code 在每个 hook 事件时调用的轻量客户端。
+//!
+//! 从 stdin 读取 hook JSON，补充 pid / 时间戳 / 主机名后转发给 HookServer。
+//! 投递失败时写入本地 spool，等 monitor 下次启动时重放。
+//! 无论发生什么错误都以 0 退出，绝不阻塞 Claude 会话。
+
+use crate::auth;
+use crate::config::ClientConfig;
+use crate::shell;
+use crate::spool;
+use crate::{discovery_path, socket_path, HookServerStatus};
+use serde_json::{Map, Value};
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use sysinfo::{Pid, System};
+use uuid::Uuid;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(300);
+const IO_TIMEOUT: Duration = Duration::from_secs(2);
+
+pub fn run() -> i32 {
+    let mut input = String::new();
+    if std::io::stdin().read_to_string(&mut input).is_err() {
+        return 0;
+    }
+
+    let Ok(Value::Object(mut event)) = serde_json::from_str::<Value>(&input) else {
+        return 0;
+    };
+    enrich(&mut event);
+
+    let config = ClientConfig::load();
+
+    // PreToolUse 可能在 monitor 中等待人工审批
+    let read_timeout = if event.get("hook_event_name").and_then(|v| v.as_str()) == Some("PreToolUse") {
+        Duration::from_secs(config.approval.timeout_secs) + IO_TIMEOUT
+    } else {
+        IO_TIMEOUT
+    };
+
+    let body = Value::Object(event).to_string();
+    let secret = client_secret().unwrap_or_default();
+    let port = discovered_port().unwrap_or(config.hook_port);
+    match post_event(port, &body, &secret, read_timeout) {
+        // PreToolUse 的权限决定通过 stdout 交给 Claude
+        Ok(response) if (200..300).contains(&response.status) => {
+            let body = response.body.trim();
+            if !body.is_empty() && body != "{}" {
+                println!("{}", body);
+            }
+        }
+        Ok(response) if response.status < 500 && response.status != 401 => {}
+        // 连接失败、密钥不匹配或服务端出错都先落盘，校验失败 (4xx) 重放也没用
+        _ => {
+            let _ = spool::append(&body);
+        }
+    }
+    0
+}
+
+/// 服务端口可能因冲突落在备用范围内，以发现文件中记录的实际端口为准
+fn discovered_port() -> Option<u16> {
+    let content = std::fs::read_to_string(discovery_path()?).ok()?;
+    serde_json::from_str::<HookServerStatus>(&content).ok()?.port
+}
+
+/// 优先读取与客户端同目录的密钥文件
+fn client_secret() -> Option<String> {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| auth::read_secret(&exe.with_file_name(auth::SECRET_FILE)))
+        .or_else(|| auth::secret_path().and_then(|path| auth::read_secret(&path)))
+}
+
+struct HookResponse {
+    status: u16,
+    body: String,
+}
+
+fn enrich(event: &mut Map<String, Value>) {
+    event.insert("event_id".into(), Uuid::new_v4().to_string().into());
+    if let Some(pid) = claude_pid() {
+        event.insert("pid".into(), pid.into());
+    }
+    if let Ok(now) = SystemTime::now().duration_since(UNIX_EPOCH) {
+        event.insert("timestamp_ms".into(), (now.as_millis() as i64).into());
+    }
+    if let Some(host) = System::host_name() {
+        event.insert("host".into(), host.into());
+    }
+}
+
+/// Claude 通过 shell 执行 hook 命令，父进程是 shell 时再往上找一层
+fn claude_pid() -> Option<u32> {
+    let mut system = System::new();
+    let own = Pid::from_u32(std::process::id());
+    system.refresh_process(own);
+    let parent = system.process(own)?.parent()?;
+
+    system.refresh_process(parent);
+    let process = system.process(parent)?;
+    if shell::is_shell(process.name()) {
+        if let Some(grandparent) = process.parent() {
+            return Some(grandparent.as_u32());
+        }
+    }
+    Some(parent.as_u32())
+}
+
+/// 优先走 Unix socket，连不上再用 TCP
+fn post_event(port: u16, body: &str, secret: &str, read_timeout: Duration) -> std::io::Result<HookResponse> {
+    #[cfg(unix)]
+    if let Some(path) = socket_path() {
+        if let Ok(stream) = std::os::unix::net::UnixStream::connect(&path) {
+            stream.set_read_timeout(Some(read_timeout))?;
+            stream.set_write_timeout(Some(IO_TIMEOUT))?;
+            return exchange(stream, "localhost", body, secret);
+        }
+    }
+
+    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+    let stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)?;
+    stream.set_read_timeout(Some(read_timeout))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    exchange(stream, &format!("127.0.0.1:{}", port), body, secret)
+}
+
+/// 手写最小的 HTTP/1.1 POST
+fn exchange<S: Read + Write>(mut stream: S, host: &str, body: &str, secret: &str) -> std::io::Result<HookResponse> {
+    let request = format!(
+        "POST /hook HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\n{}: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        host,
+        auth::SECRET_HEADER,
+        secret,
+        body.len(),
+        body
+    );
+    stream.write_all(request.as_bytes())?;
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+
+    let status = response
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| std::io::Error::other("malformed HTTP response"))?;
+    let body = response
+        .split_once("\r\n\r\n")
+        .map(|(_, body)| body.to_string())
+        .unwrap_or_default();
+
+    Ok(HookResponse { status, body })
+}
@@ -0,0 +1,59 @@
+//! monitor 配置文件中 ccm-hook 需要的字段；完整的配置结构在 monitor 端
+
+use serde::Deserialize;
+use std::path::PathBuf;
+
+pub const DEFAULT_HOOK_PORT: u16 = 9876;
+pub const DEFAULT_APPROVAL_TIMEOUT_SECS: u64 = 120;
+
+pub fn config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|d| d.join("claude-code-monitor").join("config.json"))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClientConfig {
+    #[serde(default = "default_hook_port")]
+    pub hook_port: u16,
+    #[serde(default)]
+    pub approval: ApprovalTimeout,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApprovalTimeout {
+    #[serde(default = "default_approval_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            hook_port: DEFAULT_HOOK_PORT,
+            approval: ApprovalTimeout::default(),
+        }
+    }
+}
+
+impl Default for ApprovalTimeout {
+    fn default() -> Self {
+        Self {
+            timeout_secs: DEFAULT_APPROVAL_TIMEOUT_SECS,
+        }
+    }
+}
+
+fn default_hook_port() -> u16 {
+    DEFAULT_HOOK_PORT
+}
+
+fn default_approval_timeout_secs() -> u64 {
+    DEFAULT_APPROVAL_TIMEOUT_SECS
+}
+
+impl ClientConfig {
+    pub fn load() -> Self {
+        config_path()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+}
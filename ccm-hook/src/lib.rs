@@ -0,0 +1,44 @@
+//! `ccm-hook` 客户端及其与 monitor 共用的约定：数据目录、发现文件、共享密钥、spool 和 shell 识别。
+//!
+//! 这个 crate 只依赖 serde、sysinfo 等轻量库，monitor 端从这里引用同一份定义。
+
+pub mod auth;
+pub mod client;
+pub mod config;
+pub mod shell;
+pub mod spool;
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+pub const HOOK_CLIENT_NAME: &str = if cfg!(windows) { "ccm-hook.exe" } else { "ccm-hook" };
+
+pub const SOCKET_FILE: &str = "hook.sock";
+pub const DISCOVERY_FILE: &str = "server.json";
+
+/// hook 服务实际监听的地址，同时作为发现文件的内容供 ccm-hook 读取
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HookServerStatus {
+    pub running: bool,
+    pub port: Option<u16>,
+    pub socket_path: Option<String>,
+    pub pid: u32,
+    pub error: Option<String>,
+}
+
+pub fn monitor_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|h| h.join(".claude-monitor"))
+}
+
+pub fn discovery_path() -> Option<PathBuf> {
+    monitor_dir().map(|d| d.join(DISCOVERY_FILE))
+}
+
+/// 每个用户独立的 Unix socket，优先放在 `$XDG_RUNTIME_DIR` 下
+pub fn socket_path() -> Option<PathBuf> {
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .filter(|dir| !dir.is_empty())
+        .map(|dir| PathBuf::from(dir).join("claude-code-monitor"))
+        .or_else(monitor_dir)
+        .map(|dir| dir.join(SOCKET_FILE))
+}
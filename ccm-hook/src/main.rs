@@ -0,0 +1,3 @@
+fn main() {
+    std::process::exit(ccm_hook::client::run());
+}
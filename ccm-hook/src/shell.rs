@@ -0,0 +1,19 @@
+//! 进程识别和 hook 客户端共用的可执行文件名规则
+
+pub const SHELLS: [&str; 8] = ["bash", "sh", "zsh", "fish", "dash", "cmd", "powershell", "pwsh"];
+
+/// 按去掉 `.exe` 等后缀的小写文件名判断，hook 客户端也用它跳过中间的 shell
+pub fn is_shell(path: &str) -> bool {
+    SHELLS.contains(&executable_name(path).as_str())
+}
+
+/// 路径的文件名，小写并去掉 Windows 可执行文件扩展名
+pub fn executable_name(path: &str) -> String {
+    let name = path.rsplit(['/', '\\']).next().unwrap_or(path).to_lowercase();
+    for ext in [".exe", ".cmd", ".bat", ".ps1"] {
+        if let Some(stem) = name.strip_suffix(ext) {
+            return stem.to_string();
+        }
+    }
+    name
+}
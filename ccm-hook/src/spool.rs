@@ -0,0 +1,31 @@
+//! monitor 未运行时 ccm-hook 把事件追加到本地 spool，monitor 下次启动时重放
+
+use crate::monitor_dir;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+
+pub const SPOOL_FILE: &str = "spool.jsonl";
+/// 防止 monitor 长期不运行时 spool 无限增长
+const MAX_SPOOL_BYTES: u64 = 64 * 1024 * 1024;
+
+pub fn spool_path() -> Option<PathBuf> {
+    monitor_dir().map(|d| d.join(SPOOL_FILE))
+}
+
+/// hook 客户端投递失败时调用，每个事件占一行
+pub fn append(body: &str) -> std::io::Result<()> {
+    let path = spool_path().ok_or_else(|| std::io::Error::other("home directory not found"))?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    if std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0) > MAX_SPOOL_BYTES {
+        return Err(std::io::Error::other("spool is full"));
+    }
+
+    let mut line = body.replace('\n', " ");
+    line.push('\n');
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    // 单次 write，避免多个客户端并发追加时行被拆开
+    file.write_all(line.as_bytes())
+}
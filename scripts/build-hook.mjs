@@ -0,0 +1,32 @@
+// 构建 ccm-hook 并按 Tauri sidecar 的命名放到 src-tauri/binaries/ccm-hook-<target-triple>
+// 用法: node scripts/build-hook.mjs [--release] [--target <triple>]
+import { execFileSync } from 'node:child_process'
+import { copyFileSync, mkdirSync } from 'node:fs'
+import { dirname, join } from 'node:path'
+import { fileURLToPath } from 'node:url'
+
+const repo = join(dirname(fileURLToPath(import.meta.url)), '..')
+const root = join(repo, 'src-tauri')
+// 根目录是 cargo workspace，产物在根目录的 target 下
+const targetDir = process.env.CARGO_TARGET_DIR || join(repo, 'target')
+const args = process.argv.slice(2)
+const release = args.includes('--release')
+const targetIndex = args.indexOf('--target')
+const target =
+  targetIndex >= 0
+    ? args[targetIndex + 1]
+    : /^host: (\S+)$/m.exec(execFileSync('rustc', ['-vV'], { encoding: 'utf8' }))[1]
+const ext = target.includes('windows') ? '.exe' : ''
+
+const sidecar = join(root, 'binaries', `ccm-hook-${target}${ext}`)
+mkdirSync(dirname(sidecar), { recursive: true })
+
+// ccm-hook 是独立的 workspace 成员，不经过 src-tauri 的 tauri-build
+const cargoArgs = ['build', '-p', 'ccm-hook', '--target', target]
+if (release) {
+  cargoArgs.push('--release')
+}
+execFileSync('cargo', cargoArgs, { cwd: repo, stdio: 'inherit' })
+
+copyFileSync(join(targetDir, target, release ? 'release' : 'debug', `ccm-hook${ext}`), sidecar)
+console.log(`ccm-hook -> ${sidecar}`)
@@ -0,0 +1,445 @@
+//! 阈值告警：按规则检查每个实例的 CPU、内存、运行时长和 hook 空闲时长，
+//! 持续超过阈值一段时间后触发，回落后再经过回滞窗口才解除，可选挂起或终止实例。
+
+use crate::database::Database;
+use crate::events::{EventBus, MonitorEvent};
+use crate::models::{Alert, AlertStatus, ClaudeInstance};
+use crate::monitor::ProcessMonitor;
+use crate::termination::TerminationPlan;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertMetric {
+    /// 含子进程的 CPU 百分比
+    Cpu,
+    /// 含子进程的内存，单位 MB
+    Memory,
+    /// 运行时长，单位秒
+    Runtime,
+    /// 距最后一个 hook 事件（没有时从启动算起）的秒数
+    HookIdle,
+}
+
+impl AlertMetric {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertMetric::Cpu => "cpu",
+            AlertMetric::Memory => "memory",
+            AlertMetric::Runtime => "runtime",
+            AlertMetric::HookIdle => "hook_idle",
+        }
+    }
+
+    fn format(&self, value: f64) -> String {
+        match self {
+            AlertMetric::Cpu => format!("CPU {:.1}%", value),
+            AlertMetric::Memory => format!("memory {:.0} MB", value),
+            AlertMetric::Runtime => format!("running {}", format_duration(value)),
+            AlertMetric::HookIdle => format!("no hook event for {}", format_duration(value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertAction {
+    #[default]
+    None,
+    Suspend,
+    Kill,
+}
+
+impl AlertAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertAction::None => "none",
+            AlertAction::Suspend => "suspend",
+            AlertAction::Kill => "kill",
+        }
+    }
+}
+
+/// 指标持续 `for_secs` 秒超过阈值时触发；回落到阈值以下持续 `clear_after_secs` 秒后解除
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertRule {
+    pub id: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 规则所属工作区，cwd 位于该目录下才生效；为空表示全局
+    #[serde(default)]
+    pub workspace: Option<String>,
+    pub metric: AlertMetric,
+    pub threshold: f64,
+    #[serde(default)]
+    pub for_secs: u64,
+    #[serde(default)]
+    pub clear_after_secs: u64,
+    /// 触发时对实例执行的动作
+    #[serde(default)]
+    pub action: AlertAction,
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Clone)]
+pub enum AlertChange {
+    Fired(Alert, AlertAction),
+    Resolved(Alert),
+}
+
+/// 一条规则在一个实例上的状态
+#[derive(Default)]
+struct RuleState {
+    breach_since: Option<DateTime<Local>>,
+    clear_since: Option<DateTime<Local>>,
+    firing: Option<Alert>,
+}
+
+#[derive(Default)]
+pub struct AlertEngine {
+    /// 键为 (规则 id, 实例 id)
+    states: HashMap<(String, String), RuleState>,
+}
+
+impl AlertEngine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 保存配置前检查规则
+    pub fn validate_rules(rules: &[AlertRule]) -> Result<(), String> {
+        let mut ids = HashSet::new();
+        for rule in rules {
+            if rule.id.is_empty() || !ids.insert(rule.id.as_str()) {
+                return Err(format!("Alert rule id must be unique and non-empty: {:?}", rule.id));
+            }
+            if !rule.threshold.is_finite() || rule.threshold < 0.0 {
+                return Err(format!("Alert rule {}: invalid threshold", rule.id));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn needs_hook_activity(rules: &[AlertRule]) -> bool {
+        rules.iter().any(|r| r.enabled && r.metric == AlertMetric::HookIdle)
+    }
+
+    /// 每次扫描后调用；`last_hook_events` 为各实例最后一个 hook 事件的时间
+    pub fn evaluate(
+        &mut self,
+        rules: &[AlertRule],
+        instances: &[ClaudeInstance],
+        last_hook_events: &HashMap<String, DateTime<Local>>,
+        now: DateTime<Local>,
+    ) -> Vec<AlertChange> {
+        let mut changes = Vec::new();
+        let mut seen = HashSet::new();
+
+        for rule in rules.iter().filter(|r| r.enabled) {
+            for instance in instances {
+                if let Some(workspace) = &rule.workspace {
+                    if !Path::new(&instance.cwd).starts_with(workspace) {
+                        continue;
+                    }
+                }
+                let Some(value) = metric_value(rule.metric, instance, last_hook_events, now) else {
+                    continue;
+                };
+                let key = (rule.id.clone(), instance.id.clone());
+                let state = self.states.entry(key.clone()).or_default();
+                seen.insert(key);
+
+                let breached = value > rule.threshold;
+                match &mut state.firing {
+                    None if breached => {
+                        let since = *state.breach_since.get_or_insert(now);
+                        if (now - since).num_seconds() >= rule.for_secs as i64 {
+                            let alert = new_alert(rule, instance, value, now);
+                            state.firing = Some(alert.clone());
+                            state.clear_since = None;
+                            changes.push(AlertChange::Fired(alert, rule.action));
+                        }
+                    }
+                    None => state.breach_since = None,
+                    Some(alert) if breached => {
+                        alert.value = alert.value.max(value);
+                        state.clear_since = None;
+                    }
+                    Some(_) => {
+                        let since = *state.clear_since.get_or_insert(now);
+                        if (now - since).num_seconds() >= rule.clear_after_secs as i64 {
+                            if let Some(alert) = state.firing.take() {
+                                changes.push(AlertChange::Resolved(resolve(alert, now, None)));
+                            }
+                            state.breach_since = None;
+                            state.clear_since = None;
+                        }
+                    }
+                }
+            }
+        }
+
+        // 实例已退出或规则被删除、停用
+        self.states.retain(|key, state| {
+            if seen.contains(key) {
+                return true;
+            }
+            if let Some(alert) = state.firing.take() {
+                changes.push(AlertChange::Resolved(resolve(alert, now, Some("instance exited or rule removed"))));
+            }
+            false
+        });
+        changes
+    }
+}
+
+/// 执行规则配置的动作；终止要异步逐级发送信号，返回的计划由调用方启动
+pub fn run_action(
+    alert: &Alert,
+    action: AlertAction,
+    monitor: &mut ProcessMonitor,
+    db: &Database,
+    bus: &EventBus,
+) -> Result<Option<TerminationPlan>, String> {
+    match action {
+        AlertAction::None => Ok(None),
+        AlertAction::Suspend => {
+            let pid = monitor.suspend_instance(&alert.instance_id)?;
+            let detail = format!("alert rule {}", alert.rule_id);
+            db.set_instance_status(&alert.instance_id, "suspended")
+                .and_then(|_| db.insert_lifecycle_event(&alert.instance_id, "suspended", Some(&detail)))
+                .map_err(|e| format!("Database error: {}", e))?;
+            bus.publish(MonitorEvent::InstanceStatusChanged {
+                instance_id: alert.instance_id.clone(),
+                pid,
+                status: "suspended".to_string(),
+            });
+            Ok(None)
+        }
+        AlertAction::Kill => monitor.termination_plan(&alert.instance_id).map(Some),
+    }
+}
+
+fn metric_value(
+    metric: AlertMetric,
+    instance: &ClaudeInstance,
+    last_hook_events: &HashMap<String, DateTime<Local>>,
+    now: DateTime<Local>,
+) -> Option<f64> {
+    match metric {
+        AlertMetric::Cpu => Some(instance.tree_cpu_percent as f64),
+        AlertMetric::Memory => Some(instance.tree_memory_mb),
+        AlertMetric::Runtime => Some((now - instance.start_time).num_seconds().max(0) as f64),
+        AlertMetric::HookIdle => {
+            // 挂起的实例本来就不会产生事件
+            if instance.status == "suspended" {
+                return None;
+            }
+            let last = last_hook_events
+                .get(&instance.id)
+                .copied()
+                .map_or(instance.start_time, |t| t.max(instance.start_time));
+            Some((now - last).num_seconds().max(0) as f64)
+        }
+    }
+}
+
+fn new_alert(rule: &AlertRule, instance: &ClaudeInstance, value: f64, now: DateTime<Local>) -> Alert {
+    let message = rule.message.clone().unwrap_or_else(|| {
+        format!("{} exceeds {}", rule.metric.format(value), rule.metric.format(rule.threshold))
+    });
+    Alert {
+        id: Uuid::new_v4().to_string(),
+        rule_id: rule.id.clone(),
+        instance_id: instance.id.clone(),
+        pid: instance.pid,
+        cwd: instance.cwd.clone(),
+        metric: rule.metric.as_str().to_string(),
+        value,
+        threshold: rule.threshold,
+        action: rule.action.as_str().to_string(),
+        status: AlertStatus::Firing,
+        message,
+        fired_at: now,
+        resolved_at: None,
+    }
+}
+
+fn resolve(mut alert: Alert, now: DateTime<Local>, reason: Option<&str>) -> Alert {
+    alert.status = AlertStatus::Resolved;
+    alert.resolved_at = Some(now);
+    if let Some(reason) = reason {
+        alert.message = format!("{} ({})", alert.message, reason);
+    }
+    alert
+}
+
+fn format_duration(secs: f64) -> String {
+    let secs = secs as u64;
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        _ => format!("{}h{:02}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+    use serde_json::json;
+
+    fn at(secs: i64) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2026, 1, 1, 12, 0, 0).unwrap() + Duration::seconds(secs)
+    }
+
+    fn rule(value: serde_json::Value) -> AlertRule {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn instance(id: &str, cpu: f32) -> ClaudeInstance {
+        let mut instance: ClaudeInstance = serde_json::from_value(json!({
+            "id": id,
+            "pid": 4242,
+            "cwd": "/work/app",
+            "cmdline": "claude",
+            "status": "running",
+            "start_time": at(0),
+            "last_seen": at(0),
+            "cpu_percent": cpu,
+            "memory_mb": 100.0,
+            "skip_permissions": false,
+            "print_mode": false,
+            "resumed": false,
+            "add_dirs": [],
+        }))
+        .unwrap();
+        instance.tree_cpu_percent = cpu;
+        instance
+    }
+
+    fn cpu_rule() -> AlertRule {
+        rule(json!({
+            "id": "hot", "metric": "cpu", "threshold": 80.0, "for_secs": 30, "clear_after_secs": 60, "action": "suspend",
+        }))
+    }
+
+    /// 以 cpu 序列驱动引擎，返回每一步的变化
+    fn run(engine: &mut AlertEngine, rules: &[AlertRule], steps: &[(i64, f32)]) -> Vec<Vec<AlertChange>> {
+        steps
+            .iter()
+            .map(|(secs, cpu)| engine.evaluate(rules, &[instance("i1", *cpu)], &HashMap::new(), at(*secs)))
+            .collect()
+    }
+
+    #[test]
+    fn fires_only_after_for_secs() {
+        let mut engine = AlertEngine::new();
+        let changes = run(&mut engine, &[cpu_rule()], &[(0, 90.0), (10, 95.0), (25, 90.0), (30, 85.0), (35, 90.0)]);
+
+        assert!(changes[..3].iter().all(Vec::is_empty));
+        let [AlertChange::Fired(alert, action)] = changes[3].as_slice() else {
+            panic!("expected the alert to fire at 30s: {:?}", changes[3]);
+        };
+        assert_eq!(*action, AlertAction::Suspend);
+        assert_eq!(alert.fired_at, at(30));
+        assert_eq!(alert.status, AlertStatus::Firing);
+        assert!(changes[4].is_empty());
+    }
+
+    #[test]
+    fn breach_interrupted_before_for_secs_starts_over() {
+        let mut engine = AlertEngine::new();
+        let changes = run(&mut engine, &[cpu_rule()], &[(0, 90.0), (20, 50.0), (25, 90.0), (50, 90.0), (55, 90.0)]);
+
+        assert!(changes[..4].iter().all(Vec::is_empty));
+        assert!(matches!(changes[4].as_slice(), [AlertChange::Fired(..)]));
+    }
+
+    #[test]
+    fn short_dip_does_not_clear_and_peak_is_kept() {
+        let mut engine = AlertEngine::new();
+        let changes = run(
+            &mut engine,
+            &[cpu_rule()],
+            &[(0, 90.0), (30, 90.0), (40, 99.0), (50, 10.0), (100, 10.0), (105, 95.0), (110, 10.0), (160, 10.0), (170, 10.0)],
+        );
+
+        assert!(matches!(changes[1].as_slice(), [AlertChange::Fired(..)]));
+        // 50s 起回落，100s 时未满 60s；105s 再次超限，清除计时重新开始
+        assert!(changes[2..8].iter().all(Vec::is_empty), "{:?}", changes);
+        let [AlertChange::Resolved(alert)] = changes[8].as_slice() else {
+            panic!("expected the alert to resolve at 170s: {:?}", changes[8]);
+        };
+        assert_eq!(alert.status, AlertStatus::Resolved);
+        assert_eq!(alert.resolved_at, Some(at(170)));
+        assert_eq!(alert.value, 99.0);
+        assert_eq!(alert.fired_at, at(30));
+    }
+
+    #[test]
+    fn resolves_when_instance_exits_or_rule_is_removed() {
+        let rules = [cpu_rule()];
+        let mut engine = AlertEngine::new();
+        run(&mut engine, &rules, &[(0, 90.0), (30, 90.0)]);
+        let changes = engine.evaluate(&rules, &[], &HashMap::new(), at(35));
+        let [AlertChange::Resolved(alert)] = changes.as_slice() else {
+            panic!("expected the alert to resolve after exit: {:?}", changes);
+        };
+        assert_eq!(alert.resolved_at, Some(at(35)));
+        assert!(alert.message.ends_with("(instance exited or rule removed)"));
+
+        let mut engine = AlertEngine::new();
+        run(&mut engine, &rules, &[(0, 90.0), (30, 90.0)]);
+        let changes = engine.evaluate(&[], &[instance("i1", 90.0)], &HashMap::new(), at(35));
+        assert!(matches!(changes.as_slice(), [AlertChange::Resolved(_)]));
+
+        // 停用和删除一样处理
+        let mut engine = AlertEngine::new();
+        run(&mut engine, &rules, &[(0, 90.0), (30, 90.0)]);
+        let mut disabled = cpu_rule();
+        disabled.enabled = false;
+        let changes = engine.evaluate(&[disabled], &[instance("i1", 90.0)], &HashMap::new(), at(35));
+        assert!(matches!(changes.as_slice(), [AlertChange::Resolved(_)]));
+    }
+
+    #[test]
+    fn hook_idle_is_skipped_while_suspended() {
+        let rules = [rule(json!({ "id": "idle", "metric": "hook_idle", "threshold": 600.0 }))];
+        let mut last_events = HashMap::new();
+        last_events.insert("i1".to_string(), at(100));
+
+        let mut engine = AlertEngine::new();
+        let running = instance("i1", 0.0);
+        assert!(engine.evaluate(&rules, std::slice::from_ref(&running), &last_events, at(600)).is_empty());
+        let changes = engine.evaluate(&rules, &[running], &last_events, at(701));
+        assert!(matches!(changes.as_slice(), [AlertChange::Fired(alert, AlertAction::None)] if alert.value == 601.0));
+
+        let mut engine = AlertEngine::new();
+        let mut suspended = instance("i1", 0.0);
+        suspended.status = "suspended".to_string();
+        assert!(engine.evaluate(&rules, &[suspended], &last_events, at(5000)).is_empty());
+    }
+
+    #[test]
+    fn workspace_rules_only_apply_inside_the_workspace() {
+        let mut scoped = cpu_rule();
+        scoped.for_secs = 0;
+        scoped.workspace = Some("/work/other".to_string());
+        let mut engine = AlertEngine::new();
+        assert!(engine.evaluate(std::slice::from_ref(&scoped), &[instance("i1", 99.0)], &HashMap::new(), at(0)).is_empty());
+
+        scoped.workspace = Some("/work".to_string());
+        let changes = engine.evaluate(&[scoped], &[instance("i1", 99.0)], &HashMap::new(), at(5));
+        assert!(matches!(changes.as_slice(), [AlertChange::Fired(..)]));
+    }
+}
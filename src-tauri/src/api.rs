@@ -0,0 +1,233 @@
+//! 只读 REST API：`/api/v1` 下暴露监控数据库，供脚本和内部看板使用。
+//! 与 hook 接口共用共享密钥校验，挂载在 HookServer 上。
+
+use crate::database::{Database, InstanceFilter, SessionFilter, TimeRange};
+use crate::models::{ClaudeInstance, InstanceResource, LifecycleEvent, SessionEvent, WorkspaceStats};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::get,
+    Json, Router,
+};
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+const DEFAULT_LIMIT: i64 = 50;
+const MAX_LIMIT: i64 = 500;
+
+type Db = Arc<Mutex<Database>>;
+type ApiError = (StatusCode, Json<serde_json::Value>);
+type ApiResult<T> = Result<Json<Page<T>>, ApiError>;
+
+pub fn router<S: Clone + Send + Sync + 'static>(db: Db) -> Router<S> {
+    Router::new()
+        .route("/instances", get(list_instances))
+        .route("/instances/:id", get(get_instance))
+        .route("/instances/:id/resources", get(list_resources))
+        .route("/instances/:id/sessions", get(list_instance_sessions))
+        .route("/instances/:id/lifecycle", get(list_lifecycle))
+        .route("/sessions", get(list_sessions))
+        .route("/workspaces", get(list_workspaces))
+        .route("/search", get(search))
+        .with_state(db)
+}
+
+#[derive(Debug, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub limit: i64,
+    pub offset: i64,
+    /// 还有更多数据时为下一页的 offset
+    pub next_offset: Option<i64>,
+}
+
+/// 分页与时间范围参数；from / to 接受 unix 秒或 RFC 3339 时间
+#[derive(Debug, Default, Deserialize)]
+pub struct ListParams {
+    limit: Option<i64>,
+    offset: Option<i64>,
+    from: Option<String>,
+    to: Option<String>,
+}
+
+impl ListParams {
+    fn limit(&self) -> i64 {
+        self.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT)
+    }
+
+    fn offset(&self) -> i64 {
+        self.offset.unwrap_or(0).max(0)
+    }
+
+    fn range(&self) -> Result<TimeRange, ApiError> {
+        Ok(TimeRange {
+            from: self.from.as_deref().map(parse_time).transpose()?,
+            to: self.to.as_deref().map(parse_time).transpose()?,
+        })
+    }
+
+    /// 多查一条用来判断是否还有下一页
+    fn page<T>(&self, mut items: Vec<T>) -> Page<T> {
+        let limit = self.limit();
+        let offset = self.offset();
+        let has_more = items.len() as i64 > limit;
+        items.truncate(limit as usize);
+        Page {
+            next_offset: has_more.then_some(offset + limit),
+            items,
+            limit,
+            offset,
+        }
+    }
+}
+
+// 过滤条件与 ListParams 分开提取；serde(flatten) 下 urlencoded 的数字字段无法解析
+#[derive(Debug, Deserialize)]
+pub struct InstanceParams {
+    status: Option<String>,
+    model: Option<String>,
+    permission_mode: Option<String>,
+    skip_permissions: Option<bool>,
+    print_mode: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SessionParams {
+    instance_id: Option<String>,
+    session_id: Option<String>,
+    event_type: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchParams {
+    q: String,
+}
+
+fn parse_time(value: &str) -> Result<i64, ApiError> {
+    if let Ok(secs) = value.parse::<i64>() {
+        return Ok(secs);
+    }
+    DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.timestamp())
+        .map_err(|_| error(StatusCode::BAD_REQUEST, format!("Invalid time: {}", value)))
+}
+
+fn error(status: StatusCode, message: String) -> ApiError {
+    (status, Json(serde_json::json!({ "error": message })))
+}
+
+fn db_error(e: rusqlite::Error) -> ApiError {
+    error(StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e))
+}
+
+async fn list_instances(
+    State(db): State<Db>,
+    Query(params): Query<InstanceParams>,
+    Query(list): Query<ListParams>,
+) -> ApiResult<ClaudeInstance> {
+    let filter = InstanceFilter {
+        status: params.status,
+        model: params.model,
+        permission_mode: params.permission_mode,
+        skip_permissions: params.skip_permissions,
+        print_mode: params.print_mode,
+    };
+    let db = db.lock().await;
+    let items = db
+        .list_instances(&filter, list.range()?, list.limit() + 1, list.offset())
+        .map_err(db_error)?;
+    Ok(Json(list.page(items)))
+}
+
+async fn get_instance(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+) -> Result<Json<ClaudeInstance>, ApiError> {
+    let db = db.lock().await;
+    db.get_instance(&id)
+        .map_err(db_error)?
+        .map(Json)
+        .ok_or_else(|| error(StatusCode::NOT_FOUND, format!("Instance not found: {}", id)))
+}
+
+async fn list_resources(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+    Query(params): Query<ListParams>,
+) -> ApiResult<InstanceResource> {
+    let db = db.lock().await;
+    let items = db
+        .list_resources(&id, params.range()?, params.limit() + 1, params.offset())
+        .map_err(db_error)?;
+    Ok(Json(params.page(items)))
+}
+
+async fn list_lifecycle(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+    Query(params): Query<ListParams>,
+) -> ApiResult<LifecycleEvent> {
+    let db = db.lock().await;
+    let items = db
+        .get_lifecycle_events(&id, params.range()?, params.limit() + 1, params.offset())
+        .map_err(db_error)?;
+    Ok(Json(params.page(items)))
+}
+
+async fn list_instance_sessions(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+    Query(mut params): Query<SessionParams>,
+    Query(list): Query<ListParams>,
+) -> ApiResult<SessionEvent> {
+    params.instance_id = Some(id);
+    query_sessions(db, params, list, None).await
+}
+
+async fn list_sessions(
+    State(db): State<Db>,
+    Query(params): Query<SessionParams>,
+    Query(list): Query<ListParams>,
+) -> ApiResult<SessionEvent> {
+    query_sessions(db, params, list, None).await
+}
+
+async fn search(
+    State(db): State<Db>,
+    Query(params): Query<SearchParams>,
+    Query(list): Query<ListParams>,
+) -> ApiResult<SessionEvent> {
+    query_sessions(db, SessionParams::default(), list, Some(params.q)).await
+}
+
+async fn query_sessions(
+    db: Db,
+    params: SessionParams,
+    list: ListParams,
+    text: Option<String>,
+) -> ApiResult<SessionEvent> {
+    let filter = SessionFilter {
+        instance_id: params.instance_id,
+        session_id: params.session_id,
+        event_type: params.event_type,
+        text,
+    };
+    let db = db.lock().await;
+    let items = db
+        .list_sessions(&filter, list.range()?, list.limit() + 1, list.offset())
+        .map_err(db_error)?;
+    Ok(Json(list.page(items)))
+}
+
+async fn list_workspaces(
+    State(db): State<Db>,
+    Query(params): Query<ListParams>,
+) -> ApiResult<WorkspaceStats> {
+    let db = db.lock().await;
+    let items = db
+        .list_workspaces(params.range()?, params.limit() + 1, params.offset())
+        .map_err(db_error)?;
+    Ok(Json(params.page(items)))
+}
@@ -0,0 +1,105 @@
+use crate::models::PendingApproval;
+use crate::policy::PolicyDecision;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalConfig {
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// 超时无人处理时返回给 Claude 的决定
+    #[serde(default = "default_decision")]
+    pub default_decision: PolicyDecision,
+}
+
+impl Default for ApprovalConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: default_timeout_secs(),
+            default_decision: default_decision(),
+        }
+    }
+}
+
+fn default_timeout_secs() -> u64 {
+    ccm_hook::config::DEFAULT_APPROVAL_TIMEOUT_SECS
+}
+
+fn default_decision() -> PolicyDecision {
+    PolicyDecision::Ask
+}
+
+#[derive(Debug, Clone)]
+pub struct ApprovalOutcome {
+    pub decision: PolicyDecision,
+    pub reason: String,
+}
+
+/// 等待人工审批的 PreToolUse 请求；hook 请求会一直挂起直到被处理或超时
+#[derive(Default)]
+pub struct ApprovalQueue {
+    pending: Mutex<HashMap<String, (PendingApproval, oneshot::Sender<ApprovalOutcome>)>>,
+}
+
+impl ApprovalQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn list(&self) -> Vec<PendingApproval> {
+        let pending = self.pending.lock().unwrap();
+        let mut list: Vec<PendingApproval> = pending.values().map(|(p, _)| p.clone()).collect();
+        list.sort_by_key(|p| p.created_at);
+        list
+    }
+
+    pub fn resolve(&self, id: &str, decision: PolicyDecision, reason: String) -> Result<(), String> {
+        let (_, responder) = self
+            .pending
+            .lock()
+            .unwrap()
+            .remove(id)
+            .ok_or_else(|| format!("Approval request not found: {}", id))?;
+        responder
+            .send(ApprovalOutcome { decision, reason })
+            .map_err(|_| "Tool call is no longer waiting".to_string())
+    }
+
+    /// 入队并等待审批结果，超时后返回配置的默认决定
+    pub async fn wait(&self, request: PendingApproval, config: &ApprovalConfig) -> ApprovalOutcome {
+        let (tx, rx) = oneshot::channel();
+        let _entry = PendingEntry {
+            queue: self,
+            id: request.id.clone(),
+        };
+        self.pending.lock().unwrap().insert(request.id.clone(), (request, tx));
+
+        let outcome = tokio::time::timeout(Duration::from_secs(config.timeout_secs), rx).await;
+
+        match outcome {
+            Ok(Ok(outcome)) => outcome,
+            _ => ApprovalOutcome {
+                decision: config.default_decision,
+                reason: format!(
+                    "No approval from Claude Code Monitor within {}s",
+                    config.timeout_secs
+                ),
+            },
+        }
+    }
+}
+
+/// 客户端断开时 axum 直接丢弃 handler 的 future，靠 Drop 把请求移出队列
+struct PendingEntry<'a> {
+    queue: &'a ApprovalQueue,
+    id: String,
+}
+
+impl Drop for PendingEntry<'_> {
+    fn drop(&mut self) {
+        self.queue.pending.lock().unwrap().remove(&self.id);
+    }
+}
@@ -0,0 +1,106 @@
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::Response,
+};
+use ccm_hook::auth::{read_secret, secret_path, SECRET_HEADER};
+use std::io::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// 读取共享密钥，不存在时生成一个 256 位随机密钥并以 0600 权限写入
+pub fn load_or_create_secret() -> std::io::Result<String> {
+    let path = secret_path().ok_or_else(|| std::io::Error::other("home directory not found"))?;
+    if let Some(secret) = read_secret(&path) {
+        return Ok(secret);
+    }
+
+    let secret = generate_secret()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&path)?;
+    file.write_all(secret.as_bytes())?;
+
+    Ok(secret)
+}
+
+fn generate_secret() -> std::io::Result<String> {
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes).map_err(|e| std::io::Error::other(e.to_string()))?;
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// 比较耗时与内容无关，避免通过响应时间逐字节猜测密钥
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+pub struct AuthGuard {
+    secret: String,
+    rejected: AtomicU64,
+}
+
+impl AuthGuard {
+    pub fn new(secret: String) -> Self {
+        Self {
+            secret,
+            rejected: AtomicU64::new(0),
+        }
+    }
+
+    /// 密钥文件无法读写时退回仅在内存中的随机密钥，此时客户端请求都会被拒绝并写入 spool
+    pub fn load() -> Self {
+        let secret = load_or_create_secret()
+            .or_else(|e| {
+                eprintln!("Failed to load hook secret: {}", e);
+                generate_secret()
+            })
+            .expect("Failed to generate hook secret");
+        Self::new(secret)
+    }
+
+    pub fn rejected_requests(&self) -> u64 {
+        self.rejected.load(Ordering::Relaxed)
+    }
+
+    fn check(&self, provided: Option<&str>) -> bool {
+        provided.is_some_and(|p| constant_time_eq(p.as_bytes(), self.secret.as_bytes()))
+    }
+}
+
+pub async fn require_secret(
+    State(guard): State<Arc<AuthGuard>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    // Prometheus 等工具只能配置 Authorization 头，也接受 `Bearer <secret>`
+    let headers = request.headers();
+    let provided = headers
+        .get(SECRET_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .or_else(|| {
+            headers
+                .get(axum::http::header::AUTHORIZATION)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.strip_prefix("Bearer "))
+        });
+
+    if guard.check(provided) {
+        Ok(next.run(request).await)
+    } else {
+        guard.rejected.fetch_add(1, Ordering::Relaxed);
+        Err(StatusCode::UNAUTHORIZED)
+    }
+}
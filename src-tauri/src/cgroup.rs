@@ -0,0 +1,373 @@
+//! Linux cgroup v2 资源限制：把实例及其子进程移入用户 systemd 委派的 cgroup 子树，
+//! 按配置写入 cpu.max、memory.max、pids.max，并读取用量和节流统计。
+//!
+//! 委派子树是 systemd 用户实例下的一个 scope（Delegate=yes），由一个常驻的占位进程维持，
+//! 监控重启后继续沿用，已放入的实例不受影响。
+
+use crate::models::CgroupStats;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+#[cfg(target_os = "linux")]
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+#[cfg(target_os = "linux")]
+const SCOPE_UNIT: &str = "claude-code-monitor-limits.scope";
+/// 占位进程所在的叶子 cgroup
+const HOLDER: &str = "holder";
+#[cfg(target_os = "linux")]
+const CONTROLLERS: [&str; 3] = ["cpu", "memory", "pids"];
+const CPU_PERIOD_USEC: u64 = 100_000;
+/// 准备委派子树失败后的重试间隔，每次失败翻倍
+const SETUP_RETRY_MIN: Duration = Duration::from_secs(30);
+const SETUP_RETRY_MAX: Duration = Duration::from_secs(30 * 60);
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResourceLimits {
+    /// CPU 配额，100 表示一个核
+    #[serde(default)]
+    pub cpu_percent: Option<u32>,
+    #[serde(default)]
+    pub memory_max_mb: Option<u64>,
+    #[serde(default)]
+    pub pids_max: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitScope {
+    /// 每个实例一个 cgroup，限额各自计算
+    #[default]
+    Instance,
+    /// 同一工作区的实例共用一个 cgroup 和限额
+    Workspace,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceLimits {
+    pub path: String,
+    #[serde(flatten)]
+    pub limits: ResourceLimits,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CgroupConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub scope: LimitScope,
+    /// 默认限额
+    #[serde(default)]
+    pub limits: ResourceLimits,
+    /// 按工作区覆盖默认限额，取路径前缀最长的一条
+    #[serde(default)]
+    pub workspaces: Vec<WorkspaceLimits>,
+}
+
+impl CgroupConfig {
+    pub fn limits_for(&self, cwd: &str) -> &ResourceLimits {
+        self.workspaces
+            .iter()
+            .filter(|w| Path::new(cwd).starts_with(&w.path))
+            .max_by_key(|w| w.path.len())
+            .map(|w| &w.limits)
+            .unwrap_or(&self.limits)
+    }
+}
+
+/// 一个受限的实例或工作区
+struct Group {
+    path: PathBuf,
+    limits: ResourceLimits,
+}
+
+/// 委派给监控的 scope 目录，首次启用资源限制时创建。
+/// 准备过程要调用 systemctl、systemd-run 并等待 scope 出现，由监控循环在拿 monitor 锁之前调用；
+/// 失败后按退避间隔重试，没有可用的 systemd 用户实例时不会每个周期都重跑
+#[derive(Default)]
+pub struct CgroupRoot {
+    path: Option<PathBuf>,
+    retry_at: Option<Instant>,
+    retry_delay: Duration,
+}
+
+impl CgroupRoot {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 返回委派子树；失败时输出错误，退避期间直接返回 None
+    pub async fn get(&mut self) -> Option<PathBuf> {
+        if let Some(path) = &self.path {
+            return Some(path.clone());
+        }
+        if self.retry_at.is_some_and(|at| Instant::now() < at) {
+            return None;
+        }
+        match setup().await {
+            Ok(path) => {
+                self.path = Some(path.clone());
+                self.retry_at = None;
+                self.retry_delay = Duration::ZERO;
+                Some(path)
+            }
+            Err(e) => {
+                self.retry_delay = (self.retry_delay * 2).clamp(SETUP_RETRY_MIN, SETUP_RETRY_MAX);
+                self.retry_at = Some(Instant::now() + self.retry_delay);
+                eprintln!(
+                    "Failed to set up resource limits, retrying in {}s: {}",
+                    self.retry_delay.as_secs(),
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    /// 关闭资源限制后重新启用时立即重试
+    pub fn reset_backoff(&mut self) {
+        self.retry_at = None;
+        self.retry_delay = Duration::ZERO;
+    }
+}
+
+#[derive(Default)]
+pub struct CgroupManager {
+    groups: HashMap<String, Group>,
+}
+
+impl CgroupManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 把进程放进 `root` 下 `name` 对应的 cgroup 并更新限额；已在其中的进程不再移动，之后派生的子进程自动继承
+    pub fn place(&mut self, root: &Path, name: &str, pids: &[u32], limits: &ResourceLimits) -> Result<(), String> {
+        if !self.groups.contains_key(name) {
+            let path = root.join(name);
+            if !path.is_dir() {
+                fs::create_dir(&path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+            }
+            write_limits(&path, limits)?;
+            self.groups.insert(name.to_string(), Group { path, limits: limits.clone() });
+        }
+        let group = self.groups.get_mut(name).expect("group inserted above");
+        if group.limits != *limits {
+            write_limits(&group.path, limits)?;
+            group.limits = limits.clone();
+        }
+
+        for pid in pids {
+            if current_cgroup(*pid).as_deref() != Some(group.path.as_path()) {
+                move_process(&group.path, *pid)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn stats(&self, name: &str) -> Option<CgroupStats> {
+        self.groups.get(name).map(|group| read_stats(name, &group.path))
+    }
+
+    /// 删除不再使用的 cgroup；仍有进程（如实例退出后留下的子进程）时删除失败，下次再试
+    pub fn remove_unused(&mut self, active: &HashSet<String>) {
+        self.groups
+            .retain(|name, group| active.contains(name) || fs::remove_dir(&group.path).is_err());
+    }
+
+    /// 关闭资源限制后解除所有限额，进程留在原 cgroup 中不再受限
+    pub fn release_all(&mut self) {
+        for (_, group) in self.groups.drain() {
+            if let Err(e) = write_limits(&group.path, &ResourceLimits::default()) {
+                eprintln!("Failed to release resource limits: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+async fn setup() -> Result<PathBuf, String> {
+    use std::io::ErrorKind;
+
+    if !Path::new(CGROUP_ROOT).join("cgroup.controllers").exists() {
+        return Err("cgroup v2 (unified hierarchy) is not mounted".to_string());
+    }
+    let scope = match delegated_scope().await {
+        Some(scope) => scope,
+        None => start_scope().await?,
+    };
+
+    // 有进程的 cgroup 不能给子 cgroup 启用控制器，把占位进程移到叶子节点
+    let holder = scope.join(HOLDER);
+    if let Err(e) = fs::create_dir(&holder) {
+        if e.kind() != ErrorKind::AlreadyExists {
+            return Err(format!("Failed to create {}: {}", holder.display(), e));
+        }
+    }
+    let procs = fs::read_to_string(scope.join("cgroup.procs")).unwrap_or_default();
+    for pid in procs.lines().filter_map(|line| line.trim().parse().ok()) {
+        move_process(&holder, pid)?;
+    }
+
+    let available = fs::read_to_string(scope.join("cgroup.controllers")).unwrap_or_default();
+    let enable: Vec<String> = CONTROLLERS
+        .iter()
+        .filter(|c| available.split_whitespace().any(|a| a == **c))
+        .map(|c| format!("+{}", c))
+        .collect();
+    if enable.is_empty() {
+        return Err(format!(
+            "No cpu, memory or pids controller is delegated to {}; check Delegate= of user@.service",
+            scope.display()
+        ));
+    }
+    write(&scope, "cgroup.subtree_control", &enable.join(" "))?;
+
+    // 上次运行留下的空 cgroup
+    if let Ok(entries) = fs::read_dir(&scope) {
+        for entry in entries.flatten() {
+            if entry.file_name() != HOLDER && entry.path().is_dir() {
+                let _ = fs::remove_dir(entry.path());
+            }
+        }
+    }
+    Ok(scope)
+}
+
+#[cfg(not(target_os = "linux"))]
+async fn setup() -> Result<PathBuf, String> {
+    Err("Resource limits require Linux cgroup v2".to_string())
+}
+
+/// 已在运行的委派 scope
+#[cfg(target_os = "linux")]
+async fn delegated_scope() -> Option<PathBuf> {
+    let output = tokio::process::Command::new("systemctl")
+        .args(["--user", "show", "--property=ControlGroup", "--value", SCOPE_UNIT])
+        .output()
+        .await
+        .ok()?;
+    let control_group = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if control_group.is_empty() {
+        return None;
+    }
+    let path = Path::new(CGROUP_ROOT).join(control_group.trim_start_matches('/'));
+    path.is_dir().then_some(path)
+}
+
+/// 让 systemd 用户实例创建 Delegate=yes 的 scope；scope 没有进程时会被回收，用 sleep 占位
+#[cfg(target_os = "linux")]
+async fn start_scope() -> Result<PathBuf, String> {
+    use std::process::Stdio;
+
+    let mut child = tokio::process::Command::new("systemd-run")
+        .args(["--user", "--scope", "--quiet", "--collect", "--property=Delegate=yes"])
+        .arg(format!("--unit={}", SCOPE_UNIT))
+        .args(["sleep", "infinity"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to run systemd-run: {}", e))?;
+
+    let deadline = Instant::now() + Duration::from_secs(3);
+    while Instant::now() < deadline {
+        if let Some(scope) = delegated_scope().await {
+            return Ok(scope);
+        }
+        if let Ok(Some(status)) = child.try_wait() {
+            return Err(format!("systemd-run exited with {}; is the systemd user instance running?", status));
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    Err(format!("Timed out waiting for {}", SCOPE_UNIT))
+}
+
+/// 进程当前所在的 cgroup 目录
+#[cfg(target_os = "linux")]
+fn current_cgroup(pid: u32) -> Option<PathBuf> {
+    let content = fs::read_to_string(format!("/proc/{}/cgroup", pid)).ok()?;
+    let path = content.lines().find_map(|line| line.strip_prefix("0::"))?;
+    Some(Path::new(CGROUP_ROOT).join(path.trim_start_matches('/')))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn current_cgroup(_pid: u32) -> Option<PathBuf> {
+    None
+}
+
+fn move_process(path: &Path, pid: u32) -> Result<(), String> {
+    match fs::write(path.join("cgroup.procs"), pid.to_string()) {
+        Ok(()) => Ok(()),
+        // 进程已经退出
+        #[cfg(unix)]
+        Err(e) if e.raw_os_error() == Some(libc::ESRCH) => Ok(()),
+        Err(e) => Err(format!("Failed to move pid {} into {}: {}", pid, path.display(), e)),
+    }
+}
+
+fn write_limits(path: &Path, limits: &ResourceLimits) -> Result<(), String> {
+    let cpu_quota = limits
+        .cpu_percent
+        .map(|percent| (percent as u64 * CPU_PERIOD_USEC / 100).max(1000));
+    write(path, "cpu.max", &format!("{} {}", limit_value(cpu_quota), CPU_PERIOD_USEC))?;
+    write(path, "memory.max", &limit_value(limits.memory_max_mb.map(|mb| mb * 1024 * 1024)))?;
+    write(path, "pids.max", &limit_value(limits.pids_max))
+}
+
+fn limit_value(limit: Option<u64>) -> String {
+    match limit {
+        Some(value) => value.to_string(),
+        None => "max".to_string(),
+    }
+}
+
+fn write(path: &Path, file: &str, value: &str) -> Result<(), String> {
+    fs::write(path.join(file), value).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => format!("{} is not available under {}; controller not delegated", file, path.display()),
+        _ => format!("Failed to write {} to {}: {}", value, path.join(file).display(), e),
+    })
+}
+
+fn read_stats(name: &str, path: &Path) -> CgroupStats {
+    let cpu = read_keyed(&path.join("cpu.stat"));
+    let memory_events = read_keyed(&path.join("memory.events"));
+    let cpu_max_percent = fs::read_to_string(path.join("cpu.max")).ok().and_then(|content| {
+        let mut fields = content.split_whitespace();
+        let quota: f64 = fields.next()?.parse().ok()?;
+        let period: f64 = fields.next()?.parse().ok()?;
+        Some(quota / period * 100.0)
+    });
+
+    CgroupStats {
+        name: name.to_string(),
+        cpu_usage_usec: cpu.get("usage_usec").copied().unwrap_or_default(),
+        nr_throttled: cpu.get("nr_throttled").copied().unwrap_or_default(),
+        throttled_usec: cpu.get("throttled_usec").copied().unwrap_or_default(),
+        cpu_max_percent,
+        memory_bytes: read_value(&path.join("memory.current")).unwrap_or_default(),
+        memory_max_bytes: read_value(&path.join("memory.max")),
+        oom_kills: memory_events.get("oom_kill").copied().unwrap_or_default(),
+        pids: read_value(&path.join("pids.current")).unwrap_or_default(),
+        pids_max: read_value(&path.join("pids.max")),
+    }
+}
+
+/// 单值文件；"max" 或读取失败时为 None
+fn read_value(path: &Path) -> Option<u64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// `key value` 每行一对的文件，如 cpu.stat、memory.events
+fn read_keyed(path: &Path) -> HashMap<String, u64> {
+    fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(' ')?;
+            Some((key.to_string(), value.trim().parse().ok()?))
+        })
+        .collect()
+}
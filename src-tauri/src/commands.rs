@@ -1,8 +1,18 @@
+use crate::alerts::AlertEngine;
+use crate::auth;
 use crate::config::AppConfig;
+use crate::database::TimeRange;
+use crate::events::MonitorEvent;
+use crate::hook_installer::HookInstaller;
 use crate::installer::ClaudeInstaller;
 use crate::models::*;
+use crate::notifier;
+use crate::policy::{PolicyDecision, PolicyEngine};
+use crate::termination;
 use crate::AppState;
-use tauri::{command, State};
+use ccm_hook::HOOK_CLIENT_NAME;
+use std::path::{Path, PathBuf};
+use tauri::{command, State, Window};
 
 #[command]
 pub async fn check_claude_installed() -> Result<InstallationStatus, String> {
@@ -67,85 +77,118 @@ pub async fn get_instance_sessions(
         .map_err(|e| format!("Database error: {}", e))
 }
 
+/// 按实例 id 终止，进度通过 `monitor:termination_progress` 事件推送
 #[command]
 pub async fn kill_instance(
     state: State<'_, AppState>,
-    pid: u32,
+    instance_id: String,
 ) -> Result<(), String> {
-    let mut monitor = state.monitor.lock().await;
-    monitor.kill_process(pid)
+    let plan = state.monitor.lock().await.termination_plan(&instance_id)?;
+    tauri::async_runtime::spawn(termination::terminate(
+        plan,
+        AppConfig::load().termination,
+        state.db.clone(),
+        state.events.clone(),
+        state.monitor.clone(),
+    ));
+    Ok(())
 }
 
 #[command]
-pub async fn get_hook_script(port: u16) -> Result<String, String> {
-    let script = format!(
-        r#"#!/bin/bash
-# Claude Code Monitor Hook Script
-# This script sends events to the monitor at http://localhost:{port}
+pub async fn suspend_instance(state: State<'_, AppState>, instance_id: String) -> Result<(), String> {
+    let pid = state.monitor.lock().await.suspend_instance(&instance_id)?;
+    record_status_change(&state, &instance_id, pid, "suspended").await
+}
 
-HOOK_URL="http://localhost:{port}/hook"
-PID=$$
-CWD="$(pwd)"
+#[command]
+pub async fn resume_instance(state: State<'_, AppState>, instance_id: String) -> Result<(), String> {
+    let pid = state.monitor.lock().await.resume_instance(&instance_id)?;
+    record_status_change(&state, &instance_id, pid, "running").await
+}
 
-send_hook() {{
-    local event_type="$1"
-    local data="$2"
-
-    curl -s -X POST "$HOOK_URL" \
-        -H "Content-Type: application/json" \
-        -d "{{\"
-            event\": \"$event_type\", \"
-            pid\": $PID, \"
-            cwd\": \"$CWD\", \"
-            timestamp\": $(date +%s), \"
-            data\": $data
-        }}" > /dev/null 2>&1 || true
-}}
+async fn record_status_change(
+    state: &State<'_, AppState>,
+    instance_id: &str,
+    pid: u32,
+    status: &str,
+) -> Result<(), String> {
+    let db = state.db.lock().await;
+    let action = if status == "suspended" { "suspended" } else { "resumed" };
+    db.set_instance_status(instance_id, status)
+        .and_then(|_| db.insert_lifecycle_event(instance_id, action, None))
+        .map_err(|e| format!("Database error: {}", e))?;
+    state.events.publish(MonitorEvent::InstanceStatusChanged {
+        instance_id: instance_id.to_string(),
+        pid,
+        status: status.to_string(),
+    });
+    Ok(())
+}
 
-# Hook into various events
-case "$1" in
-    start)
-        send_hook "task_start" "null"
-        ;;
-    end)
-        send_hook "task_end" "null"
-        ;;
-    prompt)
-        send_hook "prompt" "{{\\"content\\": \\"$2\\"}}"
-        ;;
-    response)
-        send_hook "response" "{{\\"content\\": \\"$2\\"}}"
-        ;;
-    *)
-        send_hook "$1" "null"
-        ;;
-esac
-"#
-    );
-    Ok(script)
+#[command]
+pub async fn get_lifecycle_events(
+    state: State<'_, AppState>,
+    instance_id: String,
+) -> Result<Vec<LifecycleEvent>, String> {
+    let db = state.db.lock().await;
+    db.get_lifecycle_events(&instance_id, TimeRange::default(), 100, 0)
+        .map_err(|e| format!("Database error: {}", e))
 }
 
+/// `status` 为 firing / resolved，不传表示全部
 #[command]
-pub async fn install_hook(
+pub async fn get_alerts(
     state: State<'_, AppState>,
-) -> Result<String, String> {
-    // 获取 hook 服务端口
-    let hook_server = state.hook_server.lock().await;
-    let port = 9876; // 默认端口
-    drop(hook_server);
+    status: Option<String>,
+    limit: i64,
+) -> Result<Vec<Alert>, String> {
+    let db = state.db.lock().await;
+    db.list_alerts(status.as_deref(), limit)
+        .map_err(|e| format!("Database error: {}", e))
+}
 
-    // 获取 hook 脚本内容
-    let script = get_hook_script(port).await?;
+#[command]
+pub async fn get_instance_children(
+    state: State<'_, AppState>,
+    instance_id: String,
+) -> Result<Vec<ChildProcess>, String> {
+    let monitor = state.monitor.lock().await;
+    monitor
+        .children_of(&instance_id)
+        .ok_or_else(|| format!("Instance not running: {}", instance_id))
+}
 
-    // 保存到用户目录
-    let hook_dir = dirs::home_dir()
+#[command]
+pub async fn get_orphan_processes(state: State<'_, AppState>) -> Result<Vec<OrphanProcess>, String> {
+    let monitor = state.monitor.lock().await;
+    Ok(monitor.orphans())
+}
+
+/// externalBin 去掉目标三元组后与主程序放在同一目录，解析方式与 Tauri 的 sidecar 相同；
+/// 开发构建时 tauri-build 同样会把它复制到 target 下主程序旁边
+fn bundled_hook_client() -> Result<PathBuf, String> {
+    let exe = tauri::utils::platform::current_exe().map_err(|e| e.to_string())?;
+    let dir = exe.parent().ok_or("Cannot determine executable directory")?;
+    Ok(dir.join(HOOK_CLIENT_NAME))
+}
+
+#[command]
+pub async fn install_hook(project_dir: Option<String>) -> Result<String, String> {
+    // ccm-hook 作为 sidecar 随安装包发布，复制到用户目录，避免应用升级或移动后路径失效
+    let source = bundled_hook_client()?;
+    if !source.exists() {
+        return Err(format!("Hook client not found: {}", source.display()));
+    }
+
+    let bin_dir = dirs::home_dir()
         .ok_or("Cannot find home directory")?
-        .join(".claude-monitor");
+        .join(".claude-monitor")
+        .join("bin");
 
-    std::fs::create_dir_all(&hook_dir).map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(&bin_dir).map_err(|e| e.to_string())?;
 
-    let hook_path = hook_dir.join("hook.sh");
-    std::fs::write(&hook_path, script).map_err(|e| e.to_string())?;
+    let hook_path = bin_dir.join(HOOK_CLIENT_NAME);
+    std::fs::copy(&source, &hook_path).map_err(|e| e.to_string())?;
 
     // 设置可执行权限（Unix）
     #[cfg(not(target_os = "windows"))]
@@ -158,47 +201,51 @@ pub async fn install_hook(
         std::fs::set_permissions(&hook_path, perms).map_err(|e| e.to_string())?;
     }
 
-    // 创建 wrapper 脚本
-    let wrapper = format!(
-        r#"#!/bin/bash
-# Claude Code wrapper with hook support
-export CLAUDE_CODE_HOOKS="{}"
-claude "$@"
-"#,
-        hook_path.to_string_lossy()
-    );
-
-    let wrapper_path = hook_dir.join("claude-with-hook");
-    std::fs::write(&wrapper_path, wrapper).map_err(|e| e.to_string())?;
+    // 生成 hook 客户端与服务端共用的密钥
+    auth::load_or_create_secret().map_err(|e| format!("Failed to create hook secret: {}", e))?;
 
-    #[cfg(not(target_os = "windows"))]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        let mut perms = std::fs::metadata(&wrapper_path)
-            .map_err(|e| e.to_string())?
-            .permissions();
-        perms.set_mode(0o755);
-        std::fs::set_permissions(&wrapper_path, perms).map_err(|e| e.to_string())?;
-    }
+    // 写入 Claude Code 的 settings.json
+    let settings_path = HookInstaller::settings_path(project_dir.as_deref())?;
+    let backup = HookInstaller::install(
+        &settings_path,
+        &HookInstaller::monitor_command(&hook_path),
+        AppConfig::load().approval.timeout_secs,
+    )?;
 
     Ok(format!(
-        "Hook installed successfully.\nHook script: {}\nWrapper: {}",
+        "Hook installed successfully.\nHook client: {}\nSettings: {}\nBackup: {}",
         hook_path.display(),
-        wrapper_path.display()
+        settings_path.display(),
+        backup.map_or("-".to_string(), |b| b.display().to_string())
     ))
 }
 
 #[command]
-pub async fn uninstall_hook() -> Result<String, String> {
-    let hook_dir = dirs::home_dir()
-        .ok_or("Cannot find home directory")?
-        .join(".claude-monitor");
+pub async fn uninstall_hook(project_dir: Option<String>) -> Result<String, String> {
+    let settings_path = HookInstaller::settings_path(project_dir.as_deref())?;
+    let backup = HookInstaller::uninstall(&settings_path)?;
+
+    // 用户级卸载时一并清理 hook 客户端和旧版脚本
+    if project_dir.is_none() {
+        let hook_dir = dirs::home_dir()
+            .ok_or("Cannot find home directory")?
+            .join(".claude-monitor");
+        let hook_client = Path::new("bin").join(HOOK_CLIENT_NAME);
+        for path in [hook_client.as_path(), Path::new("hook.sh"), Path::new("claude-with-hook")] {
+            let path = hook_dir.join(path);
+            if path.exists() {
+                std::fs::remove_file(&path).map_err(|e| e.to_string())?;
+            }
+        }
+    }
 
-    if hook_dir.exists() {
-        std::fs::remove_dir_all(&hook_dir).map_err(|e| e.to_string())?;
-        Ok("Hook uninstalled successfully".to_string())
-    } else {
-        Ok("Hook not installed".to_string())
+    match backup {
+        Some(backup) => Ok(format!(
+            "Hook uninstalled successfully.\nSettings: {}\nBackup: {}",
+            settings_path.display(),
+            backup.display()
+        )),
+        None => Ok("Hook not installed".to_string()),
     }
 }
 
@@ -363,7 +410,7 @@ pub async fn get_workspace_stats(
     state: State<'_, AppState>,
 ) -> Result<Vec<WorkspaceStats>, String> {
     let db = state.db.lock().await;
-    db.get_workspace_stats()
+    db.list_workspaces(TimeRange::default(), -1, 0)
         .map_err(|e| format!("Database error: {}", e))
 }
 
@@ -383,11 +430,91 @@ pub fn get_config() -> AppConfig {
 }
 
 #[command]
-pub fn save_config(config: AppConfig) -> Result<(), String> {
-    config.save()
+pub fn save_config(state: State<'_, AppState>, config: AppConfig) -> Result<(), String> {
+    PolicyEngine::validate_rules(&config.policy_rules)?;
+    AlertEngine::validate_rules(&config.alert_rules)?;
+    config.notifications.validate()?;
+    if config.approval.default_decision == PolicyDecision::Queue {
+        return Err("approval.default_decision must be allow, deny or ask".to_string());
+    }
+    config.save()?;
+    state.policy.reload(&config);
+    Ok(())
+}
+
+/// 向指定渠道发送测试通知，使用已保存的配置
+#[command]
+pub async fn send_test_notification(channel_id: String) -> Result<(), String> {
+    notifier::send_test(&AppConfig::load().notifications, &channel_id).await
+}
+
+#[command]
+pub async fn get_policy_decisions(
+    state: State<'_, AppState>,
+    limit: i64,
+) -> Result<Vec<PolicyDecisionRecord>, String> {
+    let db = state.db.lock().await;
+    db.get_policy_decisions(limit)
+        .map_err(|e| format!("Database error: {}", e))
+}
+
+#[command]
+pub fn is_hook_installed(project_dir: Option<String>) -> bool {
+    HookInstaller::settings_path(project_dir.as_deref())
+        .map(|path| HookInstaller::is_installed(&path))
+        .unwrap_or(false)
+}
+
+#[command]
+pub fn list_pending_approvals(state: State<'_, AppState>) -> Vec<PendingApproval> {
+    state.approvals.list()
+}
+
+#[command]
+pub fn approve_tool_call(state: State<'_, AppState>, id: String) -> Result<(), String> {
+    state.approvals.resolve(
+        &id,
+        PolicyDecision::Allow,
+        "Approved in Claude Code Monitor".to_string(),
+    )
+}
+
+#[command]
+pub fn deny_tool_call(
+    state: State<'_, AppState>,
+    id: String,
+    reason: Option<String>,
+) -> Result<(), String> {
+    state.approvals.resolve(
+        &id,
+        PolicyDecision::Deny,
+        reason.unwrap_or_else(|| "Denied in Claude Code Monitor".to_string()),
+    )
+}
+
+#[command]
+pub fn get_rejected_hook_requests(state: State<'_, AppState>) -> u64 {
+    state.hook_auth.rejected_requests()
+}
+
+#[command]
+pub async fn get_hook_server_status(state: State<'_, AppState>) -> Result<HookServerStatus, String> {
+    Ok(state.hook_server.lock().await.status())
+}
+
+/// 当前窗口开始接收实时事件，kinds 为空时接收全部类型
+#[command]
+pub fn subscribe_events(
+    state: State<'_, AppState>,
+    window: Window,
+    kinds: Option<Vec<String>>,
+) {
+    state
+        .events
+        .subscribe_window(window.label(), kinds.unwrap_or_default());
 }
 
 #[command]
-pub fn is_hook_installed() -> bool {
-    AppConfig::is_hook_installed()
+pub fn unsubscribe_events(state: State<'_, AppState>, window: Window) {
+    state.events.unsubscribe_window(window.label());
 }
@@ -1,3 +1,9 @@
+use crate::alerts::AlertRule;
+use crate::approval::ApprovalConfig;
+use crate::cgroup::CgroupConfig;
+use crate::notifier::NotificationConfig;
+use crate::policy::PolicyRule;
+use crate::termination::TerminationConfig;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
@@ -9,6 +15,26 @@ pub struct AppConfig {
     pub auto_start_monitor: bool,
     #[serde(default)]
     pub polling_interval_secs: u64,
+    /// hook 服务首选端口，被占用时依次尝试后面的 hook_port_fallbacks 个端口
+    #[serde(default = "default_hook_port")]
+    pub hook_port: u16,
+    #[serde(default = "default_hook_port_fallbacks")]
+    pub hook_port_fallbacks: u16,
+    #[serde(default)]
+    pub policy_rules: Vec<PolicyRule>,
+    #[serde(default)]
+    pub approval: ApprovalConfig,
+    /// CPU、内存、运行时长等阈值告警
+    #[serde(default)]
+    pub alert_rules: Vec<AlertRule>,
+    #[serde(default)]
+    pub termination: TerminationConfig,
+    /// Linux 下按实例或工作区限制 CPU、内存和进程数
+    #[serde(default)]
+    pub cgroup: CgroupConfig,
+    /// 任务完成、等待输入和告警的通知渠道与路由
+    #[serde(default)]
+    pub notifications: NotificationConfig,
 }
 
 impl Default for AppConfig {
@@ -17,13 +43,30 @@ impl Default for AppConfig {
             hook_enabled: false,
             auto_start_monitor: true,
             polling_interval_secs: 5,
+            hook_port: default_hook_port(),
+            hook_port_fallbacks: default_hook_port_fallbacks(),
+            policy_rules: Vec::new(),
+            approval: ApprovalConfig::default(),
+            alert_rules: Vec::new(),
+            termination: TerminationConfig::default(),
+            cgroup: CgroupConfig::default(),
+            notifications: NotificationConfig::default(),
         }
     }
 }
 
+fn default_hook_port() -> u16 {
+    ccm_hook::config::DEFAULT_HOOK_PORT
+}
+
+fn default_hook_port_fallbacks() -> u16 {
+    10
+}
+
 impl AppConfig {
+    /// ccm-hook 也从这里读取端口和审批超时
     fn config_path() -> Option<PathBuf> {
-        dirs::config_dir().map(|d| d.join("claude-code-monitor").join("config.json"))
+        ccm_hook::config::config_path()
     }
 
     pub fn load() -> Self {
@@ -51,13 +94,4 @@ impl AppConfig {
 
         Ok(())
     }
-
-    pub fn is_hook_installed() -> bool {
-        // 检查 hook 脚本是否存在
-        if let Some(home) = dirs::home_dir() {
-            let hook_path = home.join(".claude-monitor").join("hook.sh");
-            return hook_path.exists();
-        }
-        false
-    }
 }
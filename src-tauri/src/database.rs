@@ -1,6 +1,6 @@
 use crate::models::*;
 use chrono::{DateTime, Local};
-use rusqlite::{Connection, Result};
+use rusqlite::{params, params_from_iter, types::Value, Connection, Result, Row};
 use std::path::PathBuf;
 
 pub struct Database {
@@ -73,28 +73,156 @@ impl Database {
             CREATE INDEX IF NOT EXISTS idx_resources_timestamp ON resources(timestamp);
             "
         )?;
+        self.migrate()?;
+        Ok(())
+    }
+
+    fn migrate(&self) -> Result<()> {
+        // sessions: 结构化的 hook 字段
+        self.add_column_if_missing("sessions", "session_id", "TEXT")?;
+        self.add_column_if_missing("sessions", "transcript_path", "TEXT")?;
+        self.add_column_if_missing("sessions", "tool_name", "TEXT")?;
+        self.add_column_if_missing("sessions", "tool_input", "TEXT")?;
+        self.add_column_if_missing("sessions", "tool_response", "TEXT")?;
+
+        // instances: 生命周期
+        self.add_column_if_missing("instances", "end_time", "INTEGER")?;
+        self.add_column_if_missing("instances", "exit_reason", "TEXT")?;
+        self.add_column_if_missing("instances", "runtime_secs", "INTEGER")?;
+        self.add_column_if_missing("instances", "session_id", "TEXT")?;
+
+        // instances: 命令行解析出的启动方式
+        self.add_column_if_missing("instances", "model", "TEXT")?;
+        self.add_column_if_missing("instances", "permission_mode", "TEXT")?;
+        self.add_column_if_missing("instances", "skip_permissions", "INTEGER DEFAULT 0")?;
+        self.add_column_if_missing("instances", "print_mode", "INTEGER DEFAULT 0")?;
+        self.add_column_if_missing("instances", "resumed", "INTEGER DEFAULT 0")?;
+        self.add_column_if_missing("instances", "resume_session_id", "TEXT")?;
+        self.add_column_if_missing("instances", "output_format", "TEXT")?;
+        self.add_column_if_missing("instances", "add_dirs", "TEXT")?;
+        self.add_column_if_missing("instances", "mcp_config", "TEXT")?;
+
+        // instances / resources: 含子进程的合计占用
+        for table in ["instances", "resources"] {
+            self.add_column_if_missing(table, "tree_cpu_percent", "REAL DEFAULT 0")?;
+            self.add_column_if_missing(table, "tree_memory_mb", "REAL DEFAULT 0")?;
+            self.add_column_if_missing(table, "child_count", "INTEGER DEFAULT 0")?;
+        }
+
+        // resources: 字节级磁盘 IO、线程数和文件描述符数
+        self.add_column_if_missing("resources", "disk_read_bytes", "INTEGER DEFAULT 0")?;
+        self.add_column_if_missing("resources", "disk_write_bytes", "INTEGER DEFAULT 0")?;
+        self.add_column_if_missing("resources", "thread_count", "INTEGER")?;
+        self.add_column_if_missing("resources", "fd_count", "INTEGER")?;
+        // resources: cgroup 统计（JSON）
+        self.add_column_if_missing("resources", "cgroup_stats", "TEXT")?;
+
+        self.conn.execute_batch(
+            "
+            CREATE INDEX IF NOT EXISTS idx_sessions_session_id ON sessions(session_id);
+            CREATE INDEX IF NOT EXISTS idx_sessions_tool_name ON sessions(tool_name);
+
+            CREATE TABLE IF NOT EXISTS policy_decisions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp INTEGER NOT NULL,
+                instance_id TEXT NOT NULL,
+                session_id TEXT NOT NULL,
+                cwd TEXT NOT NULL,
+                tool_name TEXT NOT NULL,
+                tool_input TEXT,
+                rule_id TEXT NOT NULL,
+                decision TEXT NOT NULL,
+                reason TEXT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_policy_decisions_timestamp ON policy_decisions(timestamp);
+
+            CREATE TABLE IF NOT EXISTS lifecycle_events (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                instance_id TEXT NOT NULL,
+                timestamp INTEGER NOT NULL,
+                action TEXT NOT NULL,
+                detail TEXT
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_lifecycle_events_instance ON lifecycle_events(instance_id, timestamp);
+
+            CREATE TABLE IF NOT EXISTS alerts (
+                id TEXT PRIMARY KEY,
+                rule_id TEXT NOT NULL,
+                instance_id TEXT NOT NULL,
+                pid INTEGER NOT NULL,
+                cwd TEXT NOT NULL,
+                metric TEXT NOT NULL,
+                value REAL NOT NULL,
+                threshold REAL NOT NULL,
+                action TEXT NOT NULL,
+                status TEXT NOT NULL,
+                message TEXT NOT NULL,
+                fired_at INTEGER NOT NULL,
+                resolved_at INTEGER
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_alerts_fired_at ON alerts(fired_at);
+            CREATE INDEX IF NOT EXISTS idx_alerts_instance ON alerts(instance_id);
+            "
+        )?;
+        Ok(())
+    }
+
+    fn add_column_if_missing(&self, table: &str, column: &str, decl: &str) -> Result<()> {
+        let mut stmt = self.conn.prepare(&format!("PRAGMA table_info({})", table))?;
+        let exists = stmt
+            .query_map([], |row| row.get::<_, String>(1))?
+            .filter_map(|name| name.ok())
+            .any(|name| name == column);
+
+        if !exists {
+            self.conn.execute(
+                &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, decl),
+                [],
+            )?;
+        }
         Ok(())
     }
 
     pub fn upsert_instance(&self, instance: &ClaudeInstance) -> Result<()> {
         self.conn.execute(
-            "INSERT INTO instances (id, pid, cwd, cmdline, status, start_time, last_seen, cpu_percent, memory_mb)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            "INSERT INTO instances (id, pid, cwd, cmdline, status, start_time, last_seen, cpu_percent, memory_mb,
+                                    tree_cpu_percent, tree_memory_mb, child_count,
+                                    model, permission_mode, skip_permissions, print_mode, resumed,
+                                    resume_session_id, output_format, add_dirs, mcp_config)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21)
              ON CONFLICT(id) DO UPDATE SET
              status = excluded.status,
              last_seen = excluded.last_seen,
              cpu_percent = excluded.cpu_percent,
-             memory_mb = excluded.memory_mb",
-            [
-                &instance.id,
-                &instance.pid.to_string(),
-                &instance.cwd,
-                instance.cmdline.as_str(),
-                &instance.status,
-                &instance.start_time.timestamp().to_string(),
-                &instance.last_seen.timestamp().to_string(),
-                &instance.cpu_percent.to_string(),
-                &instance.memory_mb.to_string(),
+             memory_mb = excluded.memory_mb,
+             tree_cpu_percent = excluded.tree_cpu_percent,
+             tree_memory_mb = excluded.tree_memory_mb,
+             child_count = excluded.child_count",
+            params![
+                instance.id,
+                instance.pid,
+                instance.cwd,
+                instance.cmdline,
+                instance.status,
+                instance.start_time.timestamp(),
+                instance.last_seen.timestamp(),
+                instance.cpu_percent,
+                instance.memory_mb,
+                instance.tree_cpu_percent,
+                instance.tree_memory_mb,
+                instance.child_count,
+                instance.launch.model,
+                instance.launch.permission_mode,
+                instance.launch.skip_permissions,
+                instance.launch.print_mode,
+                instance.launch.resumed,
+                instance.launch.resume_session_id,
+                instance.launch.output_format,
+                serde_json::to_string(&instance.launch.add_dirs).ok(),
+                instance.launch.mcp_config,
             ],
         )?;
         Ok(())
@@ -102,136 +230,532 @@ impl Database {
 
     pub fn get_instances(&self, active_only: bool) -> Result<Vec<ClaudeInstance>> {
         let sql = if active_only {
-            "SELECT id, pid, cwd, cmdline, status, start_time, last_seen, cpu_percent, memory_mb
-             FROM instances WHERE status = 'running' ORDER BY last_seen DESC"
+            format!(
+                "SELECT {} FROM instances WHERE status IN ('running', 'suspended') ORDER BY last_seen DESC",
+                INSTANCE_COLUMNS
+            )
         } else {
-            "SELECT id, pid, cwd, cmdline, status, start_time, last_seen, cpu_percent, memory_mb
-             FROM instances ORDER BY last_seen DESC LIMIT 100"
+            format!(
+                "SELECT {} FROM instances ORDER BY last_seen DESC LIMIT 100",
+                INSTANCE_COLUMNS
+            )
         };
 
-        let mut stmt = self.conn.prepare(sql)?;
-        let rows = stmt.query_map([], |row| {
-            let start_ts: i64 = row.get(5)?;
-            let last_ts: i64 = row.get(6)?;
-            Ok(ClaudeInstance {
-                id: row.get(0)?,
-                pid: row.get(1)?,
-                cwd: row.get(2)?,
-                cmdline: row.get(3)?,
-                status: row.get(4)?,
-                start_time: DateTime::from_timestamp(start_ts, 0)
-                    .map(|dt| dt.with_timezone(&Local))
-                    .unwrap_or_else(|| Local::now()),
-                last_seen: DateTime::from_timestamp(last_ts, 0)
-                    .map(|dt| dt.with_timezone(&Local))
-                    .unwrap_or_else(|| Local::now()),
-                cpu_percent: row.get(7)?,
-                memory_mb: row.get(8)?,
-            })
-        })?;
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map([], row_to_instance)?;
 
         rows.collect()
     }
 
+    pub fn get_instance(&self, id: &str) -> Result<Option<ClaudeInstance>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM instances WHERE id = ?1",
+            INSTANCE_COLUMNS
+        ))?;
+        let mut rows = stmt.query_map([id], row_to_instance)?;
+        rows.next().transpose()
+    }
+
+    /// 分页查询实例，时间范围作用于 last_seen
+    pub fn list_instances(
+        &self,
+        filter: &InstanceFilter,
+        range: TimeRange,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ClaudeInstance>> {
+        let mut sql = format!("SELECT {} FROM instances WHERE 1 = 1", INSTANCE_COLUMNS);
+        let mut args: Vec<Value> = Vec::new();
+        for (column, value) in [
+            ("status", &filter.status),
+            ("model", &filter.model),
+            ("permission_mode", &filter.permission_mode),
+        ] {
+            if let Some(value) = value {
+                sql.push_str(&format!(" AND {} = ?", column));
+                args.push(value.clone().into());
+            }
+        }
+        for (column, value) in [
+            ("skip_permissions", filter.skip_permissions),
+            ("print_mode", filter.print_mode),
+        ] {
+            if let Some(value) = value {
+                sql.push_str(&format!(" AND {} = ?", column));
+                args.push(value.into());
+            }
+        }
+        range.push_sql("last_seen", &mut sql, &mut args);
+        sql.push_str(" ORDER BY last_seen DESC LIMIT ? OFFSET ?");
+        args.push(limit.into());
+        args.push(offset.into());
+
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(params_from_iter(args), row_to_instance)?;
+        rows.collect()
+    }
+
+    pub fn find_running_instance_by_pid(&self, pid: u32) -> Result<Option<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id FROM instances WHERE pid = ?1 AND status IN ('running', 'suspended')
+             ORDER BY last_seen DESC LIMIT 1"
+        )?;
+        let mut rows = stmt.query_map([pid], |row| row.get(0))?;
+        rows.next().transpose()
+    }
+
+    pub fn find_running_instance_by_cwd(&self, cwd: &str) -> Result<Option<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id FROM instances WHERE cwd = ?1 AND status IN ('running', 'suspended')
+             ORDER BY last_seen DESC LIMIT 1"
+        )?;
+        let mut rows = stmt.query_map([cwd], |row| row.get(0))?;
+        rows.next().transpose()
+    }
+
+    pub fn find_instance_by_session(&self, session_id: &str) -> Result<Option<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT instance_id FROM sessions WHERE session_id = ?1 AND instance_id != ''
+             ORDER BY timestamp DESC LIMIT 1"
+        )?;
+        let mut rows = stmt.query_map([session_id], |row| row.get(0))?;
+        rows.next().transpose()
+    }
+
     pub fn insert_resource(&self, resource: &InstanceResource) -> Result<()> {
         self.conn.execute(
-            "INSERT INTO resources (instance_id, timestamp, cpu_percent, memory_mb, disk_read_mb, disk_write_mb)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-            [
-                &resource.instance_id,
-                &resource.timestamp.timestamp().to_string(),
-                &resource.cpu_percent.to_string(),
-                &resource.memory_mb.to_string(),
-                &resource.disk_read_mb.to_string(),
-                &resource.disk_write_mb.to_string(),
+            "INSERT INTO resources (instance_id, timestamp, cpu_percent, memory_mb, disk_read_mb, disk_write_mb,
+                                    disk_read_bytes, disk_write_bytes, thread_count, fd_count,
+                                    tree_cpu_percent, tree_memory_mb, child_count, cgroup_stats)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            params![
+                resource.instance_id,
+                resource.timestamp.timestamp(),
+                resource.cpu_percent,
+                resource.memory_mb,
+                resource.disk_read_mb as i64,
+                resource.disk_write_mb as i64,
+                resource.disk_read_bytes as i64,
+                resource.disk_write_bytes as i64,
+                resource.thread_count,
+                resource.fd_count,
+                resource.tree_cpu_percent,
+                resource.tree_memory_mb,
+                resource.child_count,
+                resource.cgroup.as_ref().and_then(|stats| serde_json::to_string(stats).ok()),
             ],
         )?;
         Ok(())
     }
 
     pub fn get_instance_resources(&self, instance_id: &str, limit: i64) -> Result<Vec<InstanceResource>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM resources WHERE instance_id = ?1 ORDER BY timestamp DESC LIMIT ?2",
+            RESOURCE_COLUMNS
+        ))?;
+
+        let rows = stmt.query_map(params![instance_id, limit], row_to_resource)?;
+
+        rows.collect()
+    }
+
+    pub fn list_resources(
+        &self,
+        instance_id: &str,
+        range: TimeRange,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<InstanceResource>> {
+        let mut sql = format!("SELECT {} FROM resources WHERE instance_id = ?", RESOURCE_COLUMNS);
+        let mut args: Vec<Value> = vec![instance_id.to_string().into()];
+        range.push_sql("timestamp", &mut sql, &mut args);
+        sql.push_str(" ORDER BY timestamp DESC LIMIT ? OFFSET ?");
+        args.push(limit.into());
+        args.push(offset.into());
+
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(params_from_iter(args), row_to_resource)?;
+        rows.collect()
+    }
+
+    /// 返回新行的 rowid；None 表示该 id 已存在（重放的重复事件）
+    pub fn insert_session_event(&self, event: &SessionEvent) -> Result<Option<i64>> {
+        let inserted = self.conn.execute(
+            "INSERT OR IGNORE INTO sessions (id, instance_id, event_type, content, timestamp, metadata,
+                                   session_id, transcript_path, tool_name, tool_input, tool_response)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                event.id,
+                event.instance_id,
+                event.event_type,
+                event.content,
+                event.timestamp.timestamp(),
+                event.metadata,
+                event.session_id,
+                event.transcript_path,
+                event.tool_name,
+                event.tool_input,
+                event.tool_response,
+            ],
+        )?;
+        Ok((inserted > 0).then(|| self.conn.last_insert_rowid()))
+    }
+
+    /// 按游标顺序读取 rowid 大于 cursor 的事件；cwd 和 pid 优先取事件元数据，其次取所属实例
+    pub fn get_session_events_after(
+        &self,
+        cursor: i64,
+        workspace: Option<&str>,
+        pid: Option<u32>,
+        event_types: Option<&[String]>,
+        limit: i64,
+    ) -> Result<Vec<StoredSessionEvent>> {
+        let mut sql = format!(
+            "SELECT s.rowid, {} FROM sessions s LEFT JOIN instances i ON i.id = s.instance_id
+             WHERE s.rowid > ?",
+            prefixed_session_columns("s")
+        );
+        let mut args: Vec<Value> = vec![cursor.into()];
+
+        if let Some(workspace) = workspace {
+            // 用前缀比较而不是 LIKE，路径中的 `_` 和 `%` 不会被当作通配符
+            let workspace = workspace.trim_end_matches(['/', '\\']);
+            let prefix = format!("{}{}", workspace, std::path::MAIN_SEPARATOR);
+            sql.push_str(
+                " AND (COALESCE(json_extract(s.metadata, '$.cwd'), i.cwd) = ?
+                   OR substr(COALESCE(json_extract(s.metadata, '$.cwd'), i.cwd), 1, ?) = ?)",
+            );
+            args.push(workspace.to_string().into());
+            args.push((prefix.chars().count() as i64).into());
+            args.push(prefix.into());
+        }
+        if let Some(pid) = pid {
+            sql.push_str(" AND COALESCE(json_extract(s.metadata, '$.pid'), i.pid) = ?");
+            args.push(i64::from(pid).into());
+        }
+        if let Some(types) = event_types {
+            sql.push_str(&format!(
+                " AND s.event_type IN ({})",
+                vec!["?"; types.len()].join(", ")
+            ));
+            args.extend(types.iter().map(|t| Value::from(t.clone())));
+        }
+        sql.push_str(" ORDER BY s.rowid LIMIT ?");
+        args.push(limit.into());
+
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(params_from_iter(args), |row| {
+            Ok(StoredSessionEvent {
+                cursor: row.get(0)?,
+                event: row_to_session_event_at(row, 1)?,
+            })
+        })?;
+
+        rows.collect()
+    }
+
+    /// 按条件分页查询会话事件，text 对内容、事件类型和工具名做模糊匹配
+    pub fn list_sessions(
+        &self,
+        filter: &SessionFilter,
+        range: TimeRange,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<SessionEvent>> {
+        let mut sql = format!("SELECT {} FROM sessions WHERE 1 = 1", SESSION_COLUMNS);
+        let mut args: Vec<Value> = Vec::new();
+        for (column, value) in [
+            ("instance_id", &filter.instance_id),
+            ("session_id", &filter.session_id),
+            ("event_type", &filter.event_type),
+        ] {
+            if let Some(value) = value {
+                sql.push_str(&format!(" AND {} = ?", column));
+                args.push(value.clone().into());
+            }
+        }
+        if let Some(text) = &filter.text {
+            sql.push_str(
+                " AND (content LIKE ? ESCAPE '\\' OR event_type LIKE ? ESCAPE '\\' OR tool_name LIKE ? ESCAPE '\\')",
+            );
+            let pattern = contains_pattern(text);
+            args.extend(vec![Value::from(pattern); 3]);
+        }
+        range.push_sql("timestamp", &mut sql, &mut args);
+        sql.push_str(" ORDER BY timestamp DESC LIMIT ? OFFSET ?");
+        args.push(limit.into());
+        args.push(offset.into());
+
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(params_from_iter(args), row_to_session_event)?;
+        rows.collect()
+    }
+
+    pub fn get_instance_sessions(&self, instance_id: &str) -> Result<Vec<SessionEvent>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM sessions WHERE instance_id = ?1 ORDER BY timestamp DESC",
+            SESSION_COLUMNS
+        ))?;
+
+        let rows = stmt.query_map([instance_id], row_to_session_event)?;
+
+        rows.collect()
+    }
+
+    pub fn insert_policy_decision(&self, record: &PolicyDecisionRecord) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO policy_decisions (timestamp, instance_id, session_id, cwd, tool_name, tool_input,
+                                           rule_id, decision, reason)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                record.timestamp.timestamp(),
+                record.instance_id,
+                record.session_id,
+                record.cwd,
+                record.tool_name,
+                record.tool_input,
+                record.rule_id,
+                record.decision,
+                record.reason,
+            ],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    pub fn get_policy_decisions(&self, limit: i64) -> Result<Vec<PolicyDecisionRecord>> {
         let mut stmt = self.conn.prepare(
-            "SELECT instance_id, timestamp, cpu_percent, memory_mb, disk_read_mb, disk_write_mb
-             FROM resources WHERE instance_id = ?1 ORDER BY timestamp DESC LIMIT ?2"
+            "SELECT id, timestamp, instance_id, session_id, cwd, tool_name, tool_input, rule_id, decision, reason
+             FROM policy_decisions ORDER BY timestamp DESC, id DESC LIMIT ?1"
         )?;
 
-        let rows = stmt.query_map([instance_id, &limit.to_string()], |row| {
+        let rows = stmt.query_map([limit], |row| {
             let ts: i64 = row.get(1)?;
-            Ok(InstanceResource {
-                instance_id: row.get(0)?,
+            Ok(PolicyDecisionRecord {
+                id: row.get(0)?,
                 timestamp: DateTime::from_timestamp(ts, 0)
                     .map(|dt| dt.with_timezone(&Local))
-                    .unwrap_or_else(|| Local::now()),
-                cpu_percent: row.get(2)?,
-                memory_mb: row.get(3)?,
-                disk_read_mb: row.get(4)?,
-                disk_write_mb: row.get(5)?,
+                    .unwrap_or_else(Local::now),
+                instance_id: row.get(2)?,
+                session_id: row.get(3)?,
+                cwd: row.get(4)?,
+                tool_name: row.get(5)?,
+                tool_input: row.get(6)?,
+                rule_id: row.get(7)?,
+                decision: row.get(8)?,
+                reason: row.get(9)?,
             })
         })?;
 
         rows.collect()
     }
 
-    pub fn insert_session_event(&self, event: &SessionEvent) -> Result<()> {
+    pub fn mark_instance_exited(&self, instance: &ClaudeInstance) -> Result<()> {
         self.conn.execute(
-            "INSERT INTO sessions (id, instance_id, event_type, content, timestamp, metadata)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-            [
-                &event.id,
-                &event.instance_id,
-                &event.event_type,
-                event.content.as_str(),
-                &event.timestamp.timestamp().to_string(),
-                event.metadata.as_deref().unwrap_or(""),
+            "UPDATE instances SET status = 'exited', end_time = ?1, exit_reason = ?2, runtime_secs = ?3
+             WHERE id = ?4",
+            params![
+                instance.end_time.map(|t| t.timestamp()),
+                instance.exit_reason,
+                instance.runtime_secs,
+                instance.id,
             ],
         )?;
         Ok(())
     }
 
-    pub fn get_instance_sessions(&self, instance_id: &str) -> Result<Vec<SessionEvent>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, instance_id, event_type, content, timestamp, metadata
-             FROM sessions WHERE instance_id = ?1 ORDER BY timestamp DESC"
+    pub fn set_instance_status(&self, instance_id: &str, status: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE instances SET status = ?1 WHERE id = ?2",
+            [status, instance_id],
         )?;
+        Ok(())
+    }
 
-        let rows = stmt.query_map([instance_id], |row| {
-            let ts: i64 = row.get(4)?;
-            Ok(SessionEvent {
+    pub fn insert_lifecycle_event(&self, instance_id: &str, action: &str, detail: Option<&str>) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO lifecycle_events (instance_id, timestamp, action, detail) VALUES (?1, ?2, ?3, ?4)",
+            params![instance_id, Local::now().timestamp(), action, detail],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_lifecycle_events(
+        &self,
+        instance_id: &str,
+        range: TimeRange,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<LifecycleEvent>> {
+        let mut sql = String::from(
+            "SELECT id, instance_id, timestamp, action, detail FROM lifecycle_events WHERE instance_id = ?",
+        );
+        let mut args: Vec<Value> = vec![instance_id.to_string().into()];
+        range.push_sql("timestamp", &mut sql, &mut args);
+        sql.push_str(" ORDER BY id DESC LIMIT ? OFFSET ?");
+        args.push(limit.into());
+        args.push(offset.into());
+
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(params_from_iter(args), |row| {
+            let ts: i64 = row.get(2)?;
+            Ok(LifecycleEvent {
                 id: row.get(0)?,
                 instance_id: row.get(1)?,
-                event_type: row.get(2)?,
-                content: row.get(3)?,
                 timestamp: DateTime::from_timestamp(ts, 0)
                     .map(|dt| dt.with_timezone(&Local))
-                    .unwrap_or_else(|| Local::now()),
-                metadata: row.get(5)?,
+                    .unwrap_or_else(Local::now),
+                action: row.get(3)?,
+                detail: row.get(4)?,
             })
         })?;
+        rows.collect()
+    }
 
+    /// 启动时调用：上次运行时仍在运行、现在已不存在的实例，按最后一次看到的时间记为退出
+    pub fn reconcile_stale_instances(&self, ids: &[String]) -> Result<usize> {
+        let mut stmt = self.conn.prepare(
+            "UPDATE instances SET status = 'exited', end_time = last_seen,
+                    exit_reason = 'exited while monitor was not running',
+                    runtime_secs = MAX(last_seen - start_time, 0)
+             WHERE id = ?1 AND status IN ('running', 'suspended')",
+        )?;
+        let mut count = 0;
+        for id in ids {
+            count += stmt.execute([id])?;
+        }
+        Ok(count)
+    }
+
+    /// 新触发的告警插入，解除时覆盖原记录
+    pub fn upsert_alert(&self, alert: &Alert) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO alerts (id, rule_id, instance_id, pid, cwd, metric, value, threshold,
+                                            action, status, message, fired_at, resolved_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            params![
+                alert.id,
+                alert.rule_id,
+                alert.instance_id,
+                alert.pid,
+                alert.cwd,
+                alert.metric,
+                alert.value,
+                alert.threshold,
+                alert.action,
+                alert.status.as_str(),
+                alert.message,
+                alert.fired_at.timestamp(),
+                alert.resolved_at.map(|t| t.timestamp()),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// `status` 为 firing / resolved，None 表示全部
+    pub fn list_alerts(&self, status: Option<&str>, limit: i64) -> Result<Vec<Alert>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, rule_id, instance_id, pid, cwd, metric, value, threshold, action, status, message,
+                    fired_at, resolved_at
+             FROM alerts WHERE ?1 IS NULL OR status = ?1 ORDER BY fired_at DESC LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(params![status, limit], |row| {
+            let fired_at: i64 = row.get(11)?;
+            let resolved_at: Option<i64> = row.get(12)?;
+            Ok(Alert {
+                id: row.get(0)?,
+                rule_id: row.get(1)?,
+                instance_id: row.get(2)?,
+                pid: row.get(3)?,
+                cwd: row.get(4)?,
+                metric: row.get(5)?,
+                value: row.get(6)?,
+                threshold: row.get(7)?,
+                action: row.get(8)?,
+                status: if row.get::<_, String>(9)? == "firing" { AlertStatus::Firing } else { AlertStatus::Resolved },
+                message: row.get(10)?,
+                fired_at: DateTime::from_timestamp(fired_at, 0)
+                    .map(|dt| dt.with_timezone(&Local))
+                    .unwrap_or_else(Local::now),
+                resolved_at: resolved_at
+                    .and_then(|ts| DateTime::from_timestamp(ts, 0))
+                    .map(|dt| dt.with_timezone(&Local)),
+            })
+        })?;
         rows.collect()
     }
 
-    pub fn update_instance_status(&self, id: &str, status: &str) -> Result<()> {
+    /// 启动时调用：告警状态不跨进程保留，上次运行时未解除的告警统一解除，条件仍满足时会重新触发
+    pub fn resolve_stale_alerts(&self) -> Result<usize> {
         self.conn.execute(
-            "UPDATE instances SET status = ?1 WHERE id = ?2",
-            [status, id],
+            "UPDATE alerts SET status = 'resolved', resolved_at = ?1,
+                    message = message || ' (monitor restarted)'
+             WHERE status = 'firing'",
+            [Local::now().timestamp()],
+        )
+    }
+
+    /// 实例最后一个 hook 事件的时间
+    pub fn last_session_event_time(&self, instance_id: &str) -> Result<Option<DateTime<Local>>> {
+        let ts: Option<i64> = self.conn.query_row(
+            "SELECT MAX(timestamp) FROM sessions WHERE instance_id = ?1",
+            [instance_id],
+            |row| row.get(0),
+        )?;
+        Ok(ts
+            .and_then(|ts| DateTime::from_timestamp(ts, 0))
+            .map(|dt| dt.with_timezone(&Local)))
+    }
+
+    /// 记录实例最近一次 hook 上报的 Claude session_id
+    pub fn set_instance_session(&self, instance_id: &str, session_id: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE instances SET session_id = ?1 WHERE id = ?2",
+            [session_id, instance_id],
         )?;
         Ok(())
     }
 
-    pub fn get_workspace_stats(&self) -> Result<Vec<WorkspaceStats>> {
+    /// 实例最近一次 SessionEnd 事件记录的原因
+    pub fn find_session_end_reason(&self, instance_id: &str) -> Result<Option<String>> {
         let mut stmt = self.conn.prepare(
-            "SELECT path, name, session_count, total_tokens, last_active
-             FROM workspaces ORDER BY last_active DESC"
+            "SELECT content FROM sessions WHERE instance_id = ?1 AND event_type = 'SessionEnd'
+             ORDER BY timestamp DESC LIMIT 1"
         )?;
+        let mut rows = stmt.query_map([instance_id], |row| row.get::<_, Option<String>>(0))?;
+        Ok(rows.next().transpose()?.flatten().filter(|r| !r.is_empty()))
+    }
 
-        let rows = stmt.query_map([], |row| {
-            let last_ts: Option<i64> = row.get(4)?;
+    /// 按工作目录汇总实例和 hook 事件：会话数按 Claude session_id 去重，没有 hook 会话的实例各算一个
+    pub fn list_workspaces(&self, range: TimeRange, limit: i64, offset: i64) -> Result<Vec<WorkspaceStats>> {
+        let mut sql = String::from(
+            "WITH activity AS (
+                 SELECT cwd, COALESCE(session_id, id) AS session_key, last_seen AS ts FROM instances
+                 UNION ALL
+                 SELECT COALESCE(json_extract(s.metadata, '$.cwd'), i.cwd), s.session_id, s.timestamp
+                 FROM sessions s LEFT JOIN instances i ON i.id = s.instance_id
+             )
+             SELECT cwd, COUNT(DISTINCT session_key), MAX(ts) AS last_active FROM activity
+             WHERE cwd IS NOT NULL AND cwd != ''
+             GROUP BY cwd HAVING 1 = 1",
+        );
+        let mut args: Vec<Value> = Vec::new();
+        range.push_sql("last_active", &mut sql, &mut args);
+        sql.push_str(" ORDER BY last_active DESC LIMIT ? OFFSET ?");
+        args.push(limit.into());
+        args.push(offset.into());
+
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(params_from_iter(args), |row| {
+            let path: String = row.get(0)?;
+            let last_ts: Option<i64> = row.get(2)?;
             Ok(WorkspaceStats {
-                path: row.get(0)?,
-                name: row.get(1)?,
-                session_count: row.get(2)?,
-                total_tokens: row.get(3)?,
+                name: std::path::Path::new(&path)
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| path.clone()),
+                path,
+                session_count: row.get(1)?,
+                total_tokens: 0,
                 last_active: last_ts.and_then(|ts| {
                     DateTime::from_timestamp(ts, 0).map(|dt| dt.with_timezone(&Local))
                 }),
@@ -242,27 +766,172 @@ impl Database {
     }
 
     pub fn search_history(&self, query: &str) -> Result<Vec<SessionEvent>> {
-        let pattern = format!("%{}%", query);
-        let mut stmt = self.conn.prepare(
-            "SELECT id, instance_id, event_type, content, timestamp, metadata
-             FROM sessions WHERE content LIKE ?1 OR event_type LIKE ?1
-             ORDER BY timestamp DESC LIMIT 50"
-        )?;
+        let pattern = contains_pattern(query);
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM sessions
+             WHERE content LIKE ?1 ESCAPE '\\' OR event_type LIKE ?1 ESCAPE '\\' OR tool_name LIKE ?1 ESCAPE '\\'
+             ORDER BY timestamp DESC LIMIT 50",
+            SESSION_COLUMNS
+        ))?;
 
-        let rows = stmt.query_map([&pattern], |row| {
-            let ts: i64 = row.get(4)?;
-            Ok(SessionEvent {
-                id: row.get(0)?,
-                instance_id: row.get(1)?,
-                event_type: row.get(2)?,
-                content: row.get(3)?,
-                timestamp: DateTime::from_timestamp(ts, 0)
-                    .map(|dt| dt.with_timezone(&Local))
-                    .unwrap_or_else(|| Local::now()),
-                metadata: row.get(5)?,
-            })
-        })?;
+        let rows = stmt.query_map([&pattern], row_to_session_event)?;
 
         rows.collect()
     }
 }
+
+/// 查询时间范围，unix 秒，两端都包含
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TimeRange {
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+}
+
+impl TimeRange {
+    fn push_sql(&self, column: &str, sql: &mut String, args: &mut Vec<Value>) {
+        if let Some(from) = self.from {
+            sql.push_str(&format!(" AND {} >= ?", column));
+            args.push(from.into());
+        }
+        if let Some(to) = self.to {
+            sql.push_str(&format!(" AND {} <= ?", column));
+            args.push(to.into());
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct InstanceFilter {
+    pub status: Option<String>,
+    pub model: Option<String>,
+    pub permission_mode: Option<String>,
+    pub skip_permissions: Option<bool>,
+    pub print_mode: Option<bool>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct SessionFilter {
+    pub instance_id: Option<String>,
+    pub session_id: Option<String>,
+    pub event_type: Option<String>,
+    pub text: Option<String>,
+}
+
+const INSTANCE_COLUMNS: &str = "id, pid, cwd, cmdline, status, start_time, last_seen, cpu_percent, memory_mb,
+     end_time, exit_reason, runtime_secs, session_id, tree_cpu_percent, tree_memory_mb, child_count,
+     model, permission_mode, skip_permissions, print_mode, resumed, resume_session_id, output_format, add_dirs, mcp_config";
+
+fn row_to_instance(row: &Row) -> Result<ClaudeInstance> {
+    let start_ts: i64 = row.get(5)?;
+    let last_ts: i64 = row.get(6)?;
+    Ok(ClaudeInstance {
+        id: row.get(0)?,
+        pid: row.get(1)?,
+        cwd: row.get(2)?,
+        cmdline: row.get(3)?,
+        status: row.get(4)?,
+        start_time: DateTime::from_timestamp(start_ts, 0)
+            .map(|dt| dt.with_timezone(&Local))
+            .unwrap_or_else(Local::now),
+        last_seen: DateTime::from_timestamp(last_ts, 0)
+            .map(|dt| dt.with_timezone(&Local))
+            .unwrap_or_else(Local::now),
+        cpu_percent: row.get(7)?,
+        memory_mb: row.get(8)?,
+        end_time: row
+            .get::<_, Option<i64>>(9)?
+            .and_then(|ts| DateTime::from_timestamp(ts, 0))
+            .map(|dt| dt.with_timezone(&Local)),
+        exit_reason: row.get(10)?,
+        runtime_secs: row.get(11)?,
+        session_id: row.get(12)?,
+        tree_cpu_percent: row.get::<_, Option<f32>>(13)?.unwrap_or_default(),
+        tree_memory_mb: row.get::<_, Option<f64>>(14)?.unwrap_or_default(),
+        child_count: row.get::<_, Option<u32>>(15)?.unwrap_or_default(),
+        children: Vec::new(),
+        launch: LaunchOptions {
+            model: row.get(16)?,
+            permission_mode: row.get(17)?,
+            skip_permissions: row.get::<_, Option<bool>>(18)?.unwrap_or_default(),
+            print_mode: row.get::<_, Option<bool>>(19)?.unwrap_or_default(),
+            resumed: row.get::<_, Option<bool>>(20)?.unwrap_or_default(),
+            resume_session_id: row.get(21)?,
+            output_format: row.get(22)?,
+            add_dirs: row
+                .get::<_, Option<String>>(23)?
+                .and_then(|dirs| serde_json::from_str(&dirs).ok())
+                .unwrap_or_default(),
+            mcp_config: row.get(24)?,
+        },
+    })
+}
+
+const RESOURCE_COLUMNS: &str = "instance_id, timestamp, cpu_percent, memory_mb, disk_read_mb, disk_write_mb,
+     disk_read_bytes, disk_write_bytes, thread_count, fd_count, tree_cpu_percent, tree_memory_mb, child_count,
+     cgroup_stats";
+
+fn row_to_resource(row: &Row) -> Result<InstanceResource> {
+    let ts: i64 = row.get(1)?;
+    Ok(InstanceResource {
+        instance_id: row.get(0)?,
+        timestamp: DateTime::from_timestamp(ts, 0)
+            .map(|dt| dt.with_timezone(&Local))
+            .unwrap_or_else(Local::now),
+        cpu_percent: row.get(2)?,
+        memory_mb: row.get(3)?,
+        disk_read_mb: row.get(4)?,
+        disk_write_mb: row.get(5)?,
+        disk_read_bytes: row.get::<_, Option<i64>>(6)?.unwrap_or_default() as u64,
+        disk_write_bytes: row.get::<_, Option<i64>>(7)?.unwrap_or_default() as u64,
+        thread_count: row.get(8)?,
+        fd_count: row.get(9)?,
+        tree_cpu_percent: row.get::<_, Option<f32>>(10)?.unwrap_or_default(),
+        tree_memory_mb: row.get::<_, Option<f64>>(11)?.unwrap_or_default(),
+        child_count: row.get::<_, Option<u32>>(12)?.unwrap_or_default(),
+        cgroup: row
+            .get::<_, Option<String>>(13)?
+            .and_then(|stats| serde_json::from_str(&stats).ok()),
+    })
+}
+
+const SESSION_COLUMNS: &str = "id, instance_id, event_type, content, timestamp, metadata,
+     session_id, transcript_path, tool_name, tool_input, tool_response";
+
+/// 子串匹配用的 LIKE 模式，转义用户输入中的 `%`、`_`，配合 `ESCAPE '\'` 使用
+fn contains_pattern(text: &str) -> String {
+    let escaped = text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+/// 给 SESSION_COLUMNS 加表别名前缀，用于联表查询
+fn prefixed_session_columns(alias: &str) -> String {
+    SESSION_COLUMNS
+        .split(',')
+        .map(|c| format!("{}.{}", alias, c.trim()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn row_to_session_event(row: &Row) -> Result<SessionEvent> {
+    row_to_session_event_at(row, 0)
+}
+
+/// SESSION_COLUMNS 从第 offset 列开始
+fn row_to_session_event_at(row: &Row, offset: usize) -> Result<SessionEvent> {
+    let ts: i64 = row.get(offset + 4)?;
+    Ok(SessionEvent {
+        id: row.get(offset)?,
+        instance_id: row.get(offset + 1)?,
+        event_type: row.get(offset + 2)?,
+        content: row.get::<_, Option<String>>(offset + 3)?.unwrap_or_default(),
+        timestamp: DateTime::from_timestamp(ts, 0)
+            .map(|dt| dt.with_timezone(&Local))
+            .unwrap_or_else(Local::now),
+        metadata: row.get(offset + 5)?,
+        session_id: row.get(offset + 6)?,
+        transcript_path: row.get(offset + 7)?,
+        tool_name: row.get(offset + 8)?,
+        tool_input: row.get(offset + 9)?,
+        tool_response: row.get(offset + 10)?,
+    })
+}
@@ -0,0 +1,293 @@
+//! Claude Code 进程识别：按规则区分原生二进制、npm 的 node 入口和各类包装进程，
+//! 把 shim → node → claude 这样的进程链归并为一个逻辑实例。
+
+use crate::process_tree::{ProcessInfo, ProcessTree};
+use ccm_hook::shell::{executable_name, SHELLS};
+use std::collections::HashSet;
+
+const NPM_PACKAGE: &str = "@anthropic-ai/claude-code";
+const NODE_RUNTIMES: [&str; 3] = ["node", "nodejs", "bun"];
+const PACKAGE_RUNNERS: [&str; 6] = ["npx", "npm", "pnpm", "pnpx", "yarn", "bunx"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessRole {
+    /// 原生安装的 `claude` 可执行文件
+    Native,
+    /// node 运行 npm 包中的 CLI 入口
+    NodeEntrypoint,
+    /// 启动 Claude 的 shell 脚本、npx 等中间进程，本身不算实例
+    Wrapper,
+}
+
+pub fn classify(process: &ProcessInfo) -> Option<ProcessRole> {
+    let Some(first) = process.cmd.first() else {
+        // 读不到命令行（如其他用户的进程）时只能看进程名
+        return (executable_name(&process.name) == "claude").then_some(ProcessRole::Native);
+    };
+
+    // npm exec 会把进程标题改成 "npm exec @anthropic-ai/claude-code"
+    if first.starts_with("npm exec") || first.starts_with("npx ") {
+        return mentions_package(process).then_some(ProcessRole::Wrapper);
+    }
+
+    let exe = executable_name(first);
+    let script = process
+        .cmd
+        .iter()
+        .skip(1)
+        .find(|arg| !arg.starts_with('-'))
+        .map(String::as_str);
+
+    if NODE_RUNTIMES.contains(&exe.as_str()) {
+        let script = script?;
+        if is_claude_script(script) {
+            return Some(ProcessRole::NodeEntrypoint);
+        }
+        // node /usr/bin/npx @anthropic-ai/claude-code
+        if PACKAGE_RUNNERS.contains(&executable_name(script).as_str()) && mentions_package(process) {
+            return Some(ProcessRole::Wrapper);
+        }
+        return None;
+    }
+    if PACKAGE_RUNNERS.contains(&exe.as_str()) {
+        return mentions_package(process).then_some(ProcessRole::Wrapper);
+    }
+    if SHELLS.contains(&exe.as_str()) {
+        // claude-with-hook、Windows 下 npm 生成的 claude.cmd 等；cmd.exe 的开关以 / 开头，逐个参数判断
+        return process
+            .cmd
+            .iter()
+            .skip(1)
+            .any(|arg| executable_name(arg).starts_with("claude"))
+            .then_some(ProcessRole::Wrapper);
+    }
+    if exe == "claude-with-hook" {
+        return Some(ProcessRole::Wrapper);
+    }
+    if exe == "claude" && !is_desktop_app(first) {
+        return Some(ProcessRole::Native);
+    }
+    None
+}
+
+/// claude 程序本身之后的参数；node 入口要跳过 node 的参数和脚本路径
+pub fn claude_arguments(process: &ProcessInfo) -> &[String] {
+    if process.cmd.is_empty() {
+        return &[];
+    }
+    if classify(process) == Some(ProcessRole::NodeEntrypoint) {
+        if let Some(script) = process.cmd.iter().skip(1).position(|arg| !arg.starts_with('-')) {
+            return &process.cmd[script + 2..];
+        }
+    }
+    &process.cmd[1..]
+}
+
+/// 返回每个逻辑实例的 pid。
+///
+/// 包装进程不算实例；祖先中已有实例的进程（node 入口再启动的 claude 等）归入祖先；
+/// `self_pid` 及其子孙进程（即监控程序自己）被排除。
+pub fn detect(tree: &ProcessTree, self_pid: u32) -> Vec<u32> {
+    let candidates: HashSet<u32> = tree
+        .processes()
+        .filter(|p| p.pid != self_pid)
+        .filter(|p| matches!(classify(p), Some(ProcessRole::Native | ProcessRole::NodeEntrypoint)))
+        .map(|p| p.pid)
+        .collect();
+
+    let mut instances: Vec<u32> = candidates
+        .iter()
+        .copied()
+        .filter(|pid| {
+            let ancestors = tree.ancestors(*pid);
+            !ancestors.contains(&self_pid) && !ancestors.iter().any(|a| candidates.contains(a))
+        })
+        .collect();
+    instances.sort_unstable();
+    instances
+}
+
+fn is_claude_script(script: &str) -> bool {
+    let normalized = script.replace('\\', "/").to_lowercase();
+    normalized.contains(&format!("{}/", NPM_PACKAGE)) || executable_name(script) == "claude"
+}
+
+fn mentions_package(process: &ProcessInfo) -> bool {
+    process.cmd.iter().any(|arg| arg.to_lowercase().contains(NPM_PACKAGE))
+}
+
+/// Claude 桌面应用的可执行文件也叫 Claude
+fn is_desktop_app(path: &str) -> bool {
+    let normalized = path.replace('\\', "/").to_lowercase();
+    normalized.contains(".app/contents/") || normalized.contains("/anthropicclaude/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 解析 `ps -eo pid,ppid,comm,args` 格式的快照
+    fn tree(snapshot: &str) -> ProcessTree {
+        let processes = snapshot
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                let mut fields = line.split_whitespace();
+                let pid = fields.next().unwrap().parse().unwrap();
+                let parent = fields.next().unwrap().parse().unwrap();
+                let name = fields.next().unwrap().to_string();
+                ProcessInfo {
+                    pid,
+                    parent: Some(parent).filter(|p| *p != 0),
+                    name,
+                    cmd: fields.map(str::to_string).collect(),
+                    start_time: 0,
+                    cpu_percent: 0.0,
+                    memory_mb: 0.0,
+                }
+            })
+            .collect();
+        ProcessTree::new(processes)
+    }
+
+    #[test]
+    fn native_binary() {
+        let tree = tree(
+            "
+            1     0    systemd  /sbin/init
+            900   1    zsh      -zsh
+            1200  900  claude   /home/dev/.local/bin/claude --resume
+            ",
+        );
+        assert_eq!(detect(&tree, 5000), vec![1200]);
+        assert_eq!(classify(tree.get(1200).unwrap()), Some(ProcessRole::Native));
+    }
+
+    #[test]
+    fn npm_entrypoint() {
+        let tree = tree(
+            "
+            1     0    systemd  /sbin/init
+            900   1    bash     -bash
+            1300  900  claude   node /usr/local/bin/claude
+            1400  900  node     /usr/bin/node /usr/lib/node_modules/@anthropic-ai/claude-code/cli.js -p hi
+            ",
+        );
+        assert_eq!(detect(&tree, 5000), vec![1300, 1400]);
+        assert_eq!(classify(tree.get(1400).unwrap()), Some(ProcessRole::NodeEntrypoint));
+    }
+
+    #[test]
+    fn npx_shim_chain_is_one_instance() {
+        let tree = tree(
+            "
+            1     0     systemd  /sbin/init
+            900   1     bash     -bash
+            2000  900   npm      npm exec @anthropic-ai/claude-code
+            2001  2000  sh       sh -c claude
+            2002  2001  claude   node /home/dev/.npm/_npx/0a1b/node_modules/.bin/claude
+            2003  2002  claude   /home/dev/.npm/_npx/0a1b/node_modules/@anthropic-ai/claude-code/vendor/claude
+            ",
+        );
+        assert_eq!(detect(&tree, 5000), vec![2002]);
+        assert_eq!(classify(tree.get(2000).unwrap()), Some(ProcessRole::Wrapper));
+        assert_eq!(classify(tree.get(2001).unwrap()), Some(ProcessRole::Wrapper));
+    }
+
+    #[test]
+    fn hook_wrapper_script() {
+        let tree = tree(
+            "
+            1     0     systemd           /sbin/init
+            900   1     bash              -bash
+            3000  900   claude-with-hook  /bin/bash /home/dev/.claude-monitor/bin/claude-with-hook
+            3001  3000  claude            /home/dev/.local/bin/claude
+            3002  3001  git               git status
+            ",
+        );
+        assert_eq!(detect(&tree, 5000), vec![3001]);
+        assert_eq!(classify(tree.get(3000).unwrap()), Some(ProcessRole::Wrapper));
+    }
+
+    #[test]
+    fn children_are_not_instances() {
+        let tree = tree(
+            "
+            1     0     systemd  /sbin/init
+            900   1     bash     -bash
+            4000  900   claude   /home/dev/.local/bin/claude
+            4001  4000  node     node /home/dev/mcp/claude-memory-server/index.js
+            4002  4000  bash     /bin/bash -c npm test
+            4003  4002  node     node /home/dev/app/node_modules/.bin/jest
+            4004  4000  ccm-hook /home/dev/.claude-monitor/bin/ccm-hook
+            ",
+        );
+        assert_eq!(detect(&tree, 5000), vec![4000]);
+        assert_eq!(classify(tree.get(4001).unwrap()), None);
+    }
+
+    #[test]
+    fn excludes_monitor_and_its_descendants() {
+        let tree = tree(
+            "
+            1     0     systemd              /sbin/init
+            5000  1     claude-code-monitor  /usr/bin/claude-code-monitor
+            5001  5000  claude               /home/dev/.local/bin/claude --version
+            6000  1     claude               /home/dev/.local/bin/claude
+            ",
+        );
+        assert_eq!(detect(&tree, 5000), vec![6000]);
+        assert_eq!(classify(tree.get(5000).unwrap()), None);
+    }
+
+    #[test]
+    fn ignores_desktop_app() {
+        let tree = tree(
+            "
+            1     0    launchd  /sbin/launchd
+            700   1    Claude   /Applications/Claude.app/Contents/MacOS/Claude
+            710   700  Claude   /Applications/Claude.app/Contents/Frameworks/Claude Helper.app/Contents/MacOS/Claude Helper
+            ",
+        );
+        assert!(detect(&tree, 5000).is_empty());
+    }
+
+    #[test]
+    fn windows_paths() {
+        let tree = tree(
+            r"
+            4     0    System    System
+            800   4    cmd.exe   C:\Windows\System32\cmd.exe /c C:\Users\dev\AppData\Roaming\npm\claude.cmd
+            810   800  node.exe  C:\nodejs\node.exe C:\Users\dev\AppData\Roaming\npm\node_modules\@anthropic-ai\claude-code\cli.js
+            ",
+        );
+        assert_eq!(detect(&tree, 5000), vec![810]);
+        assert_eq!(classify(tree.get(800).unwrap()), Some(ProcessRole::Wrapper));
+    }
+
+    #[test]
+    fn arguments_after_entrypoint() {
+        let tree = tree(
+            "
+            1     0    systemd  /sbin/init
+            1200  1    claude   /home/dev/.local/bin/claude --model opus
+            1400  1    node     node --max-old-space-size=4096 /usr/lib/node_modules/@anthropic-ai/claude-code/cli.js -p hi
+            ",
+        );
+        assert_eq!(claude_arguments(tree.get(1200).unwrap()), ["--model", "opus"]);
+        assert_eq!(claude_arguments(tree.get(1400).unwrap()), ["-p", "hi"]);
+    }
+
+    #[test]
+    fn falls_back_to_name_without_cmdline() {
+        let tree = tree(
+            "
+            1     0    systemd  /sbin/init
+            1500  1    claude
+            1600  1    node
+            ",
+        );
+        assert_eq!(detect(&tree, 5000), vec![1500]);
+    }
+}
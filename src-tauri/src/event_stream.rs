@@ -0,0 +1,253 @@
+//! `GET /events/stream`：以 Server-Sent Events 推送 hook 事件和实例生命周期变化。
+//!
+//! 支持按工作区、pid 和事件类型过滤；带 `since` 参数或 `Last-Event-ID` 请求头重连时，
+//! 先从数据库补发游标之后的 hook 事件，再接着推送实时事件。
+
+use crate::database::Database;
+use crate::events::{EventBus, EventEnvelope, MonitorEvent, EVENT_SCHEMA_VERSION};
+use crate::models::{ClaudeInstance, StoredSessionEvent};
+use axum::{
+    http::HeaderMap,
+    response::sse::{Event, KeepAlive, Sse},
+};
+use futures_util::stream::{self, Stream};
+use serde::Deserialize;
+use std::convert::Infallible;
+use std::sync::Arc;
+use tokio::sync::{broadcast, mpsc, Mutex};
+
+const REPLAY_PAGE_SIZE: i64 = 500;
+const CLIENT_BUFFER: usize = 256;
+
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct StreamQuery {
+    /// 只推送 cwd 位于该目录下的事件
+    pub workspace: Option<String>,
+    pub pid: Option<u32>,
+    /// 逗号分隔，可以是事件种类（如 `instance_exited`）或 hook 事件名（如 `PreToolUse`）
+    pub types: Option<String>,
+    /// 上次收到的 hook 事件游标
+    pub since: Option<i64>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct StreamFilter {
+    pub workspace: Option<String>,
+    pub pid: Option<u32>,
+    pub types: Vec<String>,
+}
+
+impl StreamFilter {
+    pub fn from_query(query: &StreamQuery) -> Self {
+        Self {
+            workspace: query.workspace.clone().filter(|w| !w.is_empty()),
+            pid: query.pid,
+            types: query
+                .types
+                .as_deref()
+                .unwrap_or_default()
+                .split(',')
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+                .collect(),
+        }
+    }
+
+    /// 数据库补发时使用的 event_type 条件；None 表示不限制
+    pub fn hook_event_types(&self) -> Option<Vec<String>> {
+        if self.types.is_empty() || self.types.iter().any(|t| t == "hook_event_received") {
+            None
+        } else {
+            Some(self.types.clone())
+        }
+    }
+
+    /// hook 事件的 metadata 中缺少 cwd 或 pid、而过滤条件又需要时，返回要查询的实例 id
+    pub fn instance_to_resolve<'a>(&self, event: &'a MonitorEvent) -> Option<&'a str> {
+        let MonitorEvent::HookEventReceived(stored) = event else {
+            return None;
+        };
+        let (cwd, pid) = hook_event_origin(stored);
+        let missing = (self.workspace.is_some() && cwd.is_none()) || (self.pid.is_some() && pid.is_none());
+        missing.then_some(stored.event.instance_id.as_str())
+    }
+
+    /// `instance` 为 hook 事件所属的实例，与数据库补发一样在 metadata 缺少 cwd、pid 时使用实例的值
+    pub fn matches(&self, event: &MonitorEvent, instance: Option<&ClaudeInstance>) -> bool {
+        let (cwd, pid, hook_type) = match event {
+            MonitorEvent::InstanceAppeared(instance) => (Some(instance.cwd.clone()), Some(instance.pid), None),
+            MonitorEvent::InstanceExited { pid, cwd, .. } => (Some(cwd.clone()), Some(*pid), None),
+            MonitorEvent::OrphanDetected(orphan) => (Some(orphan.cwd.clone()), Some(orphan.process.pid), None),
+            MonitorEvent::TerminationProgress(progress) => (None, Some(progress.pid), None),
+            MonitorEvent::AlertFired(alert) | MonitorEvent::AlertResolved(alert) => {
+                (Some(alert.cwd.clone()), Some(alert.pid), None)
+            }
+            MonitorEvent::InstanceStatusChanged { pid, .. } => (None, Some(*pid), None),
+            MonitorEvent::HookServerStarted(_) => (None, None, None),
+            // 资源采样频率高且没有 cwd，只有显式按类型订阅时才推送
+            MonitorEvent::ResourceSample(_) => {
+                return self.types.iter().any(|t| t == event.kind())
+                    && self.workspace.is_none()
+                    && self.pid.is_none();
+            }
+            MonitorEvent::HookEventReceived(stored) => {
+                let (cwd, pid) = hook_event_origin(stored);
+                (
+                    cwd.or_else(|| instance.map(|i| i.cwd.clone())),
+                    pid.or_else(|| instance.map(|i| i.pid)),
+                    Some(stored.event.event_type.as_str()),
+                )
+            }
+        };
+
+        if !self.types.is_empty()
+            && !self
+                .types
+                .iter()
+                .any(|t| t == event.kind() || Some(t.as_str()) == hook_type)
+        {
+            return false;
+        }
+        if let Some(workspace) = &self.workspace {
+            if !cwd.is_some_and(|cwd| std::path::Path::new(&cwd).starts_with(workspace)) {
+                return false;
+            }
+        }
+        if self.pid.is_some() && pid != self.pid {
+            return false;
+        }
+        true
+    }
+}
+
+/// hook 客户端记录在 metadata 中的 cwd 和 pid
+fn hook_event_origin(stored: &StoredSessionEvent) -> (Option<String>, Option<u32>) {
+    let metadata: Option<serde_json::Value> = stored
+        .event
+        .metadata
+        .as_deref()
+        .and_then(|m| serde_json::from_str(m).ok());
+    let cwd = metadata
+        .as_ref()
+        .and_then(|m| m.get("cwd")?.as_str().map(str::to_string));
+    let pid = metadata
+        .as_ref()
+        .and_then(|m| m.get("pid")?.as_u64())
+        .map(|p| p as u32);
+    (cwd, pid)
+}
+
+pub fn open_stream(
+    db: Arc<Mutex<Database>>,
+    bus: &EventBus,
+    query: StreamQuery,
+    headers: &HeaderMap,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let filter = StreamFilter::from_query(&query);
+    let since = query.since.or_else(|| {
+        headers
+            .get("last-event-id")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse().ok())
+    });
+
+    // 先订阅再查库，补发期间产生的实时事件不会丢失
+    let live = bus.subscribe();
+    let (tx, rx) = mpsc::channel(CLIENT_BUFFER);
+    tokio::spawn(pump(db, live, filter, since, tx));
+
+    let stream = stream::unfold(rx, |mut rx| async move { rx.recv().await.map(|event| (Ok(event), rx)) });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+async fn pump(
+    db: Arc<Mutex<Database>>,
+    mut live: broadcast::Receiver<EventEnvelope>,
+    filter: StreamFilter,
+    since: Option<i64>,
+    tx: mpsc::Sender<Event>,
+) {
+    let mut cursor = since.unwrap_or(0);
+
+    if since.is_some() {
+        loop {
+            let page = {
+                let db = db.lock().await;
+                db.get_session_events_after(
+                    cursor,
+                    filter.workspace.as_deref(),
+                    filter.pid,
+                    filter.hook_event_types().as_deref(),
+                    REPLAY_PAGE_SIZE,
+                )
+            };
+            let page = match page {
+                Ok(page) => page,
+                Err(e) => {
+                    eprintln!("Failed to replay events for stream: {}", e);
+                    break;
+                }
+            };
+            let done = (page.len() as i64) < REPLAY_PAGE_SIZE;
+            for stored in page {
+                cursor = stored.cursor;
+                // 补发的事件不经过总线，seq 为 0
+                let envelope = EventEnvelope {
+                    version: EVENT_SCHEMA_VERSION,
+                    seq: 0,
+                    timestamp: stored.event.timestamp,
+                    event: MonitorEvent::HookEventReceived(stored),
+                };
+                if tx.send(to_sse(&envelope)).await.is_err() {
+                    return;
+                }
+            }
+            if done {
+                break;
+            }
+        }
+    }
+
+    loop {
+        let envelope = match live.recv().await {
+            Ok(envelope) => envelope,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                // 告知客户端有事件被丢弃，可用最后的游标重连补齐
+                let notice = Event::default().event("lagged").data(skipped.to_string());
+                if tx.send(notice).await.is_err() {
+                    return;
+                }
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => return,
+        };
+
+        if let MonitorEvent::HookEventReceived(stored) = &envelope.event {
+            // 已在补发阶段发送过
+            if stored.cursor <= cursor {
+                continue;
+            }
+        }
+        let instance = match filter.instance_to_resolve(&envelope.event) {
+            Some(id) => db.lock().await.get_instance(id).ok().flatten(),
+            None => None,
+        };
+        if !filter.matches(&envelope.event, instance.as_ref()) {
+            continue;
+        }
+        if tx.send(to_sse(&envelope)).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// 只有 hook 事件带 id，客户端断线重连时 Last-Event-ID 即为最后的数据库游标
+fn to_sse(envelope: &EventEnvelope) -> Event {
+    let event = Event::default()
+        .event(envelope.event.kind())
+        .data(serde_json::to_string(envelope).unwrap_or_default());
+    match &envelope.event {
+        MonitorEvent::HookEventReceived(stored) => event.id(stored.cursor.to_string()),
+        _ => event,
+    }
+}
@@ -0,0 +1,153 @@
+//! 后端事件总线：监控循环和 hook 服务发布事件，Tauri 前端及其他订阅者实时接收。
+
+use crate::models::{
+    Alert, ClaudeInstance, HookServerStatus, InstanceResource, OrphanProcess, StoredSessionEvent, TerminationProgress,
+};
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager};
+use tokio::sync::broadcast;
+
+/// 事件负载格式变化时递增，前端据此忽略不认识的版本
+pub const EVENT_SCHEMA_VERSION: u32 = 1;
+const CHANNEL_CAPACITY: usize = 1024;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", content = "payload", rename_all = "snake_case")]
+pub enum MonitorEvent {
+    InstanceAppeared(ClaudeInstance),
+    InstanceExited {
+        instance_id: String,
+        pid: u32,
+        cwd: String,
+        exit_reason: Option<String>,
+        runtime_secs: Option<i64>,
+    },
+    ResourceSample(InstanceResource),
+    HookEventReceived(StoredSessionEvent),
+    OrphanDetected(OrphanProcess),
+    TerminationProgress(TerminationProgress),
+    AlertFired(Alert),
+    AlertResolved(Alert),
+    /// 挂起、恢复等不改变实例存续的状态变化
+    InstanceStatusChanged {
+        instance_id: String,
+        pid: u32,
+        status: String,
+    },
+    /// hook 服务绑定后的实际端口和 socket
+    HookServerStarted(HookServerStatus),
+}
+
+impl MonitorEvent {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            MonitorEvent::InstanceAppeared(_) => "instance_appeared",
+            MonitorEvent::InstanceExited { .. } => "instance_exited",
+            MonitorEvent::ResourceSample(_) => "resource_sample",
+            MonitorEvent::HookEventReceived(_) => "hook_event_received",
+            MonitorEvent::OrphanDetected(_) => "orphan_detected",
+            MonitorEvent::TerminationProgress(_) => "termination_progress",
+            MonitorEvent::AlertFired(_) => "alert_fired",
+            MonitorEvent::AlertResolved(_) => "alert_resolved",
+            MonitorEvent::InstanceStatusChanged { .. } => "instance_status_changed",
+            MonitorEvent::HookServerStarted(_) => "hook_server_started",
+        }
+    }
+
+    /// 对应的 Tauri 事件名，如 `monitor:instance_appeared`
+    pub fn tauri_event_name(&self) -> String {
+        format!("monitor:{}", self.kind())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EventEnvelope {
+    pub version: u32,
+    pub seq: u64,
+    pub timestamp: DateTime<Local>,
+    #[serde(flatten)]
+    pub event: MonitorEvent,
+}
+
+pub struct EventBus {
+    sender: broadcast::Sender<EventEnvelope>,
+    seq: AtomicU64,
+    /// 窗口 label -> 订阅的事件类型，空集合表示全部
+    subscriptions: Mutex<HashMap<String, HashSet<String>>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self {
+            sender,
+            seq: AtomicU64::new(0),
+            subscriptions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 没有订阅者时事件直接丢弃
+    pub fn publish(&self, event: MonitorEvent) {
+        let envelope = EventEnvelope {
+            version: EVENT_SCHEMA_VERSION,
+            seq: self.seq.fetch_add(1, Ordering::Relaxed) + 1,
+            timestamp: Local::now(),
+            event,
+        };
+        let _ = self.sender.send(envelope);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<EventEnvelope> {
+        self.sender.subscribe()
+    }
+
+    pub fn subscribe_window(&self, label: &str, kinds: Vec<String>) {
+        self.subscriptions
+            .lock()
+            .unwrap()
+            .insert(label.to_string(), kinds.into_iter().collect());
+    }
+
+    pub fn unsubscribe_window(&self, label: &str) {
+        self.subscriptions.lock().unwrap().remove(label);
+    }
+
+    fn window_targets(&self, kind: &str) -> Vec<String> {
+        self.subscriptions
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, kinds)| kinds.is_empty() || kinds.contains(kind))
+            .map(|(label, _)| label.clone())
+            .collect()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 把总线上的事件转发给已订阅的窗口
+pub fn forward_to_tauri(app: AppHandle, bus: Arc<EventBus>) {
+    let mut receiver = bus.subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let envelope = match receiver.recv().await {
+                Ok(envelope) => envelope,
+                // 前端处理不过来时丢弃积压的事件，下次刷新会从数据库补齐
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            let name = envelope.event.tauri_event_name();
+            for label in bus.window_targets(envelope.event.kind()) {
+                let _ = app.emit_to(&label, &name, envelope.clone());
+            }
+        }
+    });
+}
@@ -0,0 +1,359 @@
+use chrono::Local;
+use serde_json::{json, Map, Value};
+use std::path::{Path, PathBuf};
+
+/// 写入 settings.json 的命令都带这个标记，用于识别 monitor 自己的条目
+pub const MONITOR_TAG: &str = "--claude-code-monitor";
+
+/// 审批超时之外留给 ccm-hook 收尾的时间
+const HOOK_TIMEOUT_MARGIN_SECS: u64 = 15;
+
+/// (事件名, 是否需要 matcher)
+const HOOK_EVENTS: [(&str, bool); 9] = [
+    ("PreToolUse", true),
+    ("PostToolUse", true),
+    ("UserPromptSubmit", false),
+    ("Notification", false),
+    ("Stop", false),
+    ("SubagentStop", false),
+    ("PreCompact", false),
+    ("SessionStart", false),
+    ("SessionEnd", false),
+];
+
+pub struct HookInstaller;
+
+impl HookInstaller {
+    /// 用户级 ~/.claude/settings.json，或项目级 <project>/.claude/settings.json
+    pub fn settings_path(project_dir: Option<&str>) -> Result<PathBuf, String> {
+        match project_dir {
+            Some(dir) => {
+                let dir = PathBuf::from(dir);
+                if !dir.is_dir() {
+                    return Err(format!("Project directory not found: {}", dir.display()));
+                }
+                Ok(dir.join(".claude").join("settings.json"))
+            }
+            None => dirs::home_dir()
+                .map(|h| h.join(".claude").join("settings.json"))
+                .ok_or_else(|| "Cannot find home directory".to_string()),
+        }
+    }
+
+    pub fn monitor_command(hook_path: &Path) -> String {
+        format!("\"{}\" {}", hook_path.display(), MONITOR_TAG)
+    }
+
+    /// 合并 monitor 的 hook 条目，返回备份文件路径（原文件不存在时为 None）。
+    /// PreToolUse 可能等待人工审批，超时时间要覆盖审批超时
+    pub fn install(
+        settings_path: &Path,
+        command: &str,
+        approval_timeout_secs: u64,
+    ) -> Result<Option<PathBuf>, String> {
+        let mut settings = Self::read_settings(settings_path)?;
+        Self::remove_monitor_hooks(&mut settings);
+        Self::merge_monitor_hooks(&mut settings, command, approval_timeout_secs + HOOK_TIMEOUT_MARGIN_SECS)?;
+        Self::write_settings(settings_path, &settings)
+    }
+
+    pub fn uninstall(settings_path: &Path) -> Result<Option<PathBuf>, String> {
+        if !settings_path.exists() {
+            return Ok(None);
+        }
+        let mut settings = Self::read_settings(settings_path)?;
+        if !Self::remove_monitor_hooks(&mut settings) {
+            return Ok(None);
+        }
+        Self::write_settings(settings_path, &settings)
+    }
+
+    pub fn is_installed(settings_path: &Path) -> bool {
+        Self::read_settings(settings_path)
+            .map(|settings| {
+                settings
+                    .get("hooks")
+                    .and_then(|h| h.as_object())
+                    .is_some_and(|hooks| hooks.values().any(has_monitor_entry))
+            })
+            .unwrap_or(false)
+    }
+
+    fn read_settings(path: &Path) -> Result<Value, String> {
+        if !path.exists() {
+            return Ok(json!({}));
+        }
+        let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        if content.trim().is_empty() {
+            return Ok(json!({}));
+        }
+        let settings: Value = serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid JSON in {}: {}", path.display(), e))?;
+        if !settings.is_object() {
+            return Err(format!("{} is not a JSON object", path.display()));
+        }
+        Ok(settings)
+    }
+
+    fn write_settings(path: &Path, settings: &Value) -> Result<Option<PathBuf>, String> {
+        let backup = Self::backup(path)?;
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let content = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+        std::fs::write(path, content).map_err(|e| e.to_string())?;
+
+        Ok(backup)
+    }
+
+    fn backup(path: &Path) -> Result<Option<PathBuf>, String> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "settings.json".to_string());
+        let backup_path = path.with_file_name(format!(
+            "{}.bak-{}",
+            file_name,
+            Local::now().format("%Y%m%d-%H%M%S%.3f")
+        ));
+        std::fs::copy(path, &backup_path).map_err(|e| e.to_string())?;
+        Ok(Some(backup_path))
+    }
+
+    fn merge_monitor_hooks(settings: &mut Value, command: &str, pre_tool_timeout: u64) -> Result<(), String> {
+        let root = settings.as_object_mut().ok_or("settings is not a JSON object")?;
+        let hooks = root
+            .entry("hooks")
+            .or_insert_with(|| Value::Object(Map::new()))
+            .as_object_mut()
+            .ok_or("\"hooks\" in settings is not a JSON object")?;
+
+        for (event, needs_matcher) in HOOK_EVENTS {
+            let groups = hooks
+                .entry(event)
+                .or_insert_with(|| Value::Array(Vec::new()))
+                .as_array_mut()
+                .ok_or_else(|| format!("\"hooks.{}\" in settings is not an array", event))?;
+
+            let mut entry = json!({ "type": "command", "command": command });
+            if event == "PreToolUse" {
+                entry["timeout"] = json!(pre_tool_timeout);
+            }
+            let mut group = json!({ "hooks": [entry] });
+            if needs_matcher {
+                group["matcher"] = json!("*");
+            }
+            groups.push(group);
+        }
+        Ok(())
+    }
+
+    /// 删除带标记的条目，清理随之变空的分组；返回是否有改动
+    fn remove_monitor_hooks(settings: &mut Value) -> bool {
+        let Some(hooks) = settings.get_mut("hooks").and_then(|h| h.as_object_mut()) else {
+            return false;
+        };
+
+        let mut changed = false;
+        let mut emptied = Vec::new();
+        for (event, groups) in hooks.iter_mut() {
+            let Some(groups) = groups.as_array_mut() else {
+                continue;
+            };
+            let before = groups.len();
+            // 只清理因删除条目而变空的分组和事件，用户原本留空的保持不动
+            groups.retain_mut(|group| {
+                let Some(entries) = group.get_mut("hooks").and_then(|h| h.as_array_mut()) else {
+                    return true;
+                };
+                let count = entries.len();
+                entries.retain(|entry| !is_monitor_entry(entry));
+                if entries.len() == count {
+                    return true;
+                }
+                changed = true;
+                !entries.is_empty()
+            });
+            if groups.is_empty() && before > 0 {
+                emptied.push(event.clone());
+            }
+        }
+        for event in emptied {
+            hooks.remove(&event);
+        }
+
+        changed
+    }
+}
+
+fn is_monitor_entry(entry: &Value) -> bool {
+    entry
+        .get("command")
+        .and_then(|c| c.as_str())
+        .is_some_and(|c| c.contains(MONITOR_TAG))
+}
+
+fn has_monitor_entry(groups: &Value) -> bool {
+    groups.as_array().is_some_and(|groups| {
+        groups.iter().any(|group| {
+            group
+                .get("hooks")
+                .and_then(|h| h.as_array())
+                .is_some_and(|entries| entries.iter().any(is_monitor_entry))
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMMAND: &str = "\"/opt/ccm/ccm-hook\" --claude-code-monitor";
+
+    /// 每个测试独立的临时目录，返回其中的 settings.json 路径
+    fn settings_file(name: &str, content: Option<Value>) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ccm-hook-installer-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("settings.json");
+        if let Some(content) = content {
+            std::fs::write(&path, serde_json::to_string_pretty(&content).unwrap()).unwrap();
+        }
+        path
+    }
+
+    fn read(path: &Path) -> Value {
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    fn cleanup(path: &Path) {
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    fn user_settings() -> Value {
+        json!({
+            "model": "opus",
+            "hooks": {
+                "PreToolUse": [
+                    { "matcher": "Bash", "hooks": [{ "type": "command", "command": "~/bin/audit.sh" }] }
+                ],
+                "Stop": [
+                    { "hooks": [{ "type": "command", "command": "notify-send done" }] }
+                ]
+            }
+        })
+    }
+
+    fn tagged_entries(settings: &Value, event: &str) -> usize {
+        settings["hooks"][event]
+            .as_array()
+            .map(|groups| {
+                groups
+                    .iter()
+                    .flat_map(|g| g["hooks"].as_array().unwrap())
+                    .filter(|e| is_monitor_entry(e))
+                    .count()
+            })
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn install_keeps_user_hooks() {
+        let path = settings_file("keep", Some(user_settings()));
+        HookInstaller::install(&path, COMMAND, 300).unwrap();
+
+        let settings = read(&path);
+        cleanup(&path);
+        assert_eq!(settings["model"], "opus");
+        let pre_tool = settings["hooks"]["PreToolUse"].as_array().unwrap();
+        assert_eq!(pre_tool[0], user_settings()["hooks"]["PreToolUse"][0]);
+        assert_eq!(settings["hooks"]["Stop"][0], user_settings()["hooks"]["Stop"][0]);
+        for (event, _) in HOOK_EVENTS {
+            assert_eq!(tagged_entries(&settings, event), 1, "{}", event);
+        }
+        let entry = &pre_tool[1]["hooks"][0];
+        assert_eq!(entry["command"], COMMAND);
+        assert_eq!(entry["timeout"], 300 + HOOK_TIMEOUT_MARGIN_SECS);
+        assert_eq!(pre_tool[1]["matcher"], "*");
+    }
+
+    #[test]
+    fn install_twice_leaves_one_tagged_entry() {
+        let path = settings_file("twice", Some(user_settings()));
+        HookInstaller::install(&path, COMMAND, 300).unwrap();
+        HookInstaller::install(&path, "\"/usr/lib/ccm/ccm-hook\" --claude-code-monitor", 60).unwrap();
+
+        let settings = read(&path);
+        cleanup(&path);
+        for (event, _) in HOOK_EVENTS {
+            assert_eq!(tagged_entries(&settings, event), 1, "{}", event);
+        }
+        assert_eq!(settings["hooks"]["PreToolUse"].as_array().unwrap().len(), 2);
+        assert_eq!(
+            settings["hooks"]["PreToolUse"][1]["hooks"][0]["command"],
+            "\"/usr/lib/ccm/ccm-hook\" --claude-code-monitor"
+        );
+    }
+
+    #[test]
+    fn uninstall_removes_only_tagged_entries_and_emptied_groups() {
+        let mut original = user_settings();
+        // 与用户条目同组的 monitor 条目，以及用户原本就留空的分组
+        original["hooks"]["Stop"][0]["hooks"]
+            .as_array_mut()
+            .unwrap()
+            .push(json!({ "type": "command", "command": COMMAND }));
+        original["hooks"]["Notification"] = json!([{ "hooks": [] }]);
+        let path = settings_file("uninstall", Some(original));
+        HookInstaller::install(&path, COMMAND, 300).unwrap();
+        assert!(HookInstaller::is_installed(&path));
+
+        assert!(HookInstaller::uninstall(&path).unwrap().is_some());
+        let settings = read(&path);
+        assert!(!HookInstaller::is_installed(&path));
+        assert_eq!(HookInstaller::uninstall(&path).unwrap(), None);
+        cleanup(&path);
+
+        let mut expected = user_settings();
+        expected["hooks"]["Notification"] = json!([{ "hooks": [] }]);
+        assert_eq!(settings, expected);
+    }
+
+    #[test]
+    fn backup_is_written_before_modifying() {
+        let path = settings_file("backup", Some(user_settings()));
+        let original = std::fs::read_to_string(&path).unwrap();
+
+        let backup = HookInstaller::install(&path, COMMAND, 300).unwrap().unwrap();
+        let backup_content = std::fs::read_to_string(&backup).unwrap();
+        let installed = std::fs::read_to_string(&path).unwrap();
+        cleanup(&path);
+
+        assert_eq!(backup_content, original);
+        assert_ne!(installed, original);
+        let name = backup.file_name().unwrap().to_string_lossy().to_string();
+        let stamp = name.strip_prefix("settings.json.bak-").unwrap();
+        assert!(
+            chrono::NaiveDateTime::parse_from_str(stamp, "%Y%m%d-%H%M%S%.3f").is_ok(),
+            "{}",
+            name
+        );
+    }
+
+    #[test]
+    fn install_without_settings_file_creates_it_without_backup() {
+        let path = settings_file("fresh", None);
+        let backup = HookInstaller::install(&path, COMMAND, 300).unwrap();
+        let settings = read(&path);
+        cleanup(&path);
+
+        assert_eq!(backup, None);
+        for (event, _) in HOOK_EVENTS {
+            assert_eq!(tagged_entries(&settings, event), 1, "{}", event);
+        }
+    }
+}
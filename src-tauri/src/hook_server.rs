@@ -1,55 +1,367 @@
-use crate::models::HookEvent;
+use crate::api;
+use crate::approval::ApprovalQueue;
+use crate::auth::{self, AuthGuard};
+use crate::config::AppConfig;
+use crate::database::Database;
+use crate::event_stream::{self, StreamQuery};
+use crate::events::{EventBus, MonitorEvent};
+use crate::ingest;
+use crate::metrics::Metrics;
+use crate::models::{HookEvent, HookEventName, HookServerStatus, PendingApproval, PolicyDecisionRecord};
+use crate::policy::{PolicyDecision, PolicyEngine, PolicyMatch, PolicyStore};
+use crate::spool;
 use axum::{
-    routing::post,
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    middleware,
+    response::IntoResponse,
+    routing::{get, post},
     Json, Router,
 };
+use ccm_hook::discovery_path;
+#[cfg(unix)]
+use ccm_hook::socket_path;
 use std::net::SocketAddr;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::Duration;
+use tokio::sync::Mutex;
 use tower_http::cors::CorsLayer;
 
 pub struct HookServer {
-    port: u16,
-    events: Arc<RwLock<Vec<HookEvent>>>,
+    state: HookState,
+    status: HookServerStatus,
+}
+
+#[derive(Clone)]
+struct HookState {
+    db: Arc<Mutex<Database>>,
+    approvals: Arc<ApprovalQueue>,
+    policy: Arc<PolicyStore>,
+    auth: Arc<AuthGuard>,
+    events: Arc<EventBus>,
+    metrics: Arc<Metrics>,
 }
 
 impl HookServer {
-    pub fn new(port: u16) -> Self {
+    pub fn new(
+        db: Arc<Mutex<Database>>,
+        approvals: Arc<ApprovalQueue>,
+        policy: Arc<PolicyStore>,
+        auth: Arc<AuthGuard>,
+        events: Arc<EventBus>,
+        metrics: Arc<Metrics>,
+    ) -> Self {
         Self {
-            port,
-            events: Arc::new(RwLock::new(Vec::new())),
+            state: HookState {
+                db,
+                approvals,
+                policy,
+                auth,
+                events,
+                metrics,
+            },
+            status: HookServerStatus::default(),
         }
     }
 
-    pub async fn start(&self) {
-        let events = self.events.clone();
+    pub fn status(&self) -> HookServerStatus {
+        self.status.clone()
+    }
 
-        let app = Router::new()
+    /// 绑定端口后在后台提供服务，绑定失败时记录错误而不是让应用崩溃
+    pub async fn start(&mut self) -> Result<HookServerStatus, String> {
+        self.status = HookServerStatus {
+            pid: std::process::id(),
+            ..Default::default()
+        };
+        match self.bind_and_serve().await {
+            Ok(()) => Ok(self.status()),
+            Err(e) => {
+                self.status.error = Some(e.clone());
+                Err(e)
+            }
+        }
+    }
+
+    async fn bind_and_serve(&mut self) -> Result<(), String> {
+        // 除健康检查外都要求携带共享密钥
+        let protected = Router::new()
             .route("/hook", post(handle_hook))
+            .route("/events/stream", get(handle_stream))
+            .route("/metrics", get(handle_metrics))
+            .nest("/api/v1", api::router(self.state.db.clone()))
+            .route_layer(middleware::from_fn_with_state(
+                self.state.auth.clone(),
+                auth::require_secret,
+            ));
+
+        // 不允许任何跨域来源，网页无法借浏览器向本地服务投递事件
+        let app = Router::new()
+            .merge(protected)
             .route("/health", post(|| async { "OK" }))
-            .layer(CorsLayer::permissive())
-            .with_state(events);
+            .layer(CorsLayer::new())
+            .with_state(self.state.clone());
+
+        let config = AppConfig::load();
+        let listener = bind_tcp(config.hook_port, config.hook_port_fallbacks).await?;
+        self.status.port = listener.local_addr().ok().map(|addr| addr.port());
+
+        #[cfg(unix)]
+        match bind_unix_socket() {
+            Ok((unix_listener, path)) => {
+                self.status.socket_path = Some(path.display().to_string());
+                tokio::spawn(serve_unix(unix_listener, app.clone()));
+            }
+            Err(e) => eprintln!("Failed to bind hook socket: {}", e),
+        }
+
+        self.status.running = true;
+        if let Err(e) = write_discovery(&self.status) {
+            eprintln!("Failed to write hook discovery file: {}", e);
+        }
+        self.state
+            .events
+            .publish(MonitorEvent::HookServerStarted(self.status.clone()));
+
+        tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, app).await {
+                eprintln!("Hook server stopped: {}", e);
+            }
+        });
 
-        let addr = SocketAddr::from(([127, 0, 0, 1], self.port));
+        // 端口就绪后再重放，重放期间到达的新事件不会再被写进 spool
+        tokio::spawn(replay_spool(self.state.db.clone()));
 
-        let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
+        Ok(())
+    }
+}
 
-        axum::serve(listener, app).await.unwrap();
+/// 依次尝试首选端口和后续的备用端口
+async fn bind_tcp(port: u16, fallbacks: u16) -> Result<tokio::net::TcpListener, String> {
+    let last = port.saturating_add(fallbacks);
+    for candidate in port..=last {
+        let addr = SocketAddr::from(([127, 0, 0, 1], candidate));
+        match tokio::net::TcpListener::bind(addr).await {
+            Ok(listener) => return Ok(listener),
+            Err(e) => eprintln!("Hook port {} unavailable: {}", candidate, e),
+        }
     }
+    Err(format!("No free hook port in 127.0.0.1:{}-{}", port, last))
+}
 
-    pub async fn get_events(&self) -> Vec<HookEvent> {
-        self.events.read().await.clone()
+/// 退出时删除发现文件，避免 ccm-hook 继续尝试已经关闭的端口；
+/// 文件已被另一个 monitor 改写时保留
+pub fn remove_discovery() {
+    let Some(path) = discovery_path() else {
+        return;
+    };
+    let ours = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<HookServerStatus>(&content).ok())
+        .is_some_and(|status| status.pid == std::process::id());
+    if ours {
+        if let Err(e) = std::fs::remove_file(&path) {
+            eprintln!("Failed to remove hook discovery file: {}", e);
+        }
     }
+}
 
-    pub async fn clear_events(&self) {
-        self.events.write().await.clear();
+fn write_discovery(status: &HookServerStatus) -> std::io::Result<()> {
+    let path = discovery_path().ok_or_else(|| std::io::Error::other("home directory not found"))?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
     }
+    let content = serde_json::to_string_pretty(status).map_err(std::io::Error::other)?;
+    std::fs::write(path, content)
+}
+
+/// 绑定当前用户的 hook socket，目录 0700、socket 0600，其他用户无法连接
+#[cfg(unix)]
+fn bind_unix_socket() -> std::io::Result<(tokio::net::UnixListener, std::path::PathBuf)> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = socket_path().ok_or_else(|| std::io::Error::other("home directory not found"))?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+        std::fs::set_permissions(parent, std::fs::Permissions::from_mode(0o700))?;
+    }
+
+    if path.exists() {
+        // 仍能连上说明另一个 monitor 正在使用
+        if std::os::unix::net::UnixStream::connect(&path).is_ok() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AddrInUse,
+                format!("{} is in use by another monitor", path.display()),
+            ));
+        }
+        std::fs::remove_file(&path)?;
+    }
+
+    let listener = tokio::net::UnixListener::bind(&path)?;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+    Ok((listener, path))
+}
+
+/// axum 0.7 的 serve 只支持 TCP，Unix socket 用 hyper 逐连接处理
+#[cfg(unix)]
+async fn serve_unix(listener: tokio::net::UnixListener, app: Router) {
+    use hyper_util::rt::TokioIo;
+    use hyper_util::service::TowerToHyperService;
+
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                eprintln!("Failed to accept hook socket connection: {}", e);
+                continue;
+            }
+        };
+        let service = TowerToHyperService::new(app.clone());
+        tokio::spawn(async move {
+            if let Err(e) = hyper::server::conn::http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                eprintln!("Hook socket connection error: {}", e);
+            }
+        });
+    }
+}
+
+/// 重放 monitor 未运行期间 hook 客户端写入的 spool
+async fn replay_spool(db: Arc<Mutex<Database>>) {
+    let files = match spool::take() {
+        Ok(files) if !files.is_empty() => files,
+        Ok(_) => return,
+        Err(e) => {
+            eprintln!("Failed to take hook spool: {}", e);
+            return;
+        }
+    };
+    // 给改名前已打开 spool 的客户端留出写完的时间
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let db = db.lock().await;
+    match spool::replay(&db, &files) {
+        Ok(stats) => eprintln!(
+            "Replayed hook spool: {} events, {} duplicates, {} invalid",
+            stats.replayed, stats.duplicates, stats.invalid
+        ),
+        Err(e) => eprintln!("Failed to replay hook spool: {}", e),
+    }
+}
+
+async fn handle_stream(
+    State(state): State<HookState>,
+    headers: HeaderMap,
+    Query(query): Query<StreamQuery>,
+) -> impl IntoResponse {
+    event_stream::open_stream(state.db.clone(), &state.events, query, &headers)
+}
+
+async fn handle_metrics(State(state): State<HookState>) -> impl IntoResponse {
+    (
+        [(axum::http::header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.metrics.render(state.auth.rejected_requests()),
+    )
 }
 
 async fn handle_hook(
-    axum::extract::State(events): axum::extract::State<Arc<RwLock<Vec<HookEvent>>>>,
+    State(state): State<HookState>,
     Json(event): Json<HookEvent>,
-) -> &'static str {
-    events.write().await.push(event);
-    "OK"
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    event
+        .validate()
+        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e))?;
+    state.metrics.record_hook_event(event.hook_event_name.as_str());
+
+    // 保存配置时会重新编译规则，这里只取当前生效的一份
+    let active = state.policy.current();
+    let policy = if event.hook_event_name == HookEventName::PreToolUse {
+        active.engine.evaluate(&event)
+    } else {
+        None
+    };
+
+    let db = state.db.lock().await;
+    let started = std::time::Instant::now();
+    let stored = ingest::ingest_hook_event(&db, &event);
+    state.metrics.record_db_write(started.elapsed());
+    match &stored {
+        Ok(Some(stored_event)) => state
+            .events
+            .publish(MonitorEvent::HookEventReceived(stored_event.clone())),
+        Ok(None) => {}
+        Err(_) => state.metrics.record_ingest_error(),
+    }
+
+    let Some(mut policy) = policy else {
+        stored.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?;
+        return Ok(Json(serde_json::json!({})));
+    };
+
+    // 入库失败也要把决定返回给 Claude，规则不能因为历史记录出错而失效
+    let instance_id = match stored {
+        Ok(Some(stored_event)) => stored_event.event.instance_id,
+        Ok(None) => ingest::resolve_instance(&db, &event).ok().flatten().unwrap_or_default(),
+        Err(e) => {
+            eprintln!("Failed to store hook event: {}", e);
+            String::new()
+        }
+    };
+    drop(db);
+
+    // 审批期间不持有数据库锁
+    if policy.decision == PolicyDecision::Queue {
+        let request = pending_approval(&event, &instance_id, &policy, active.approval.timeout_secs);
+        let outcome = state.approvals.wait(request, &active.approval).await;
+        policy.decision = outcome.decision;
+        policy.reason = outcome.reason;
+    }
+
+    let db = state.db.lock().await;
+    if let Err(e) = db.insert_policy_decision(&decision_record(&event, instance_id, &policy)) {
+        eprintln!("Failed to log policy decision: {}", e);
+    }
+
+    Ok(Json(PolicyEngine::hook_output(policy.decision, &policy.reason)))
+}
+
+fn pending_approval(
+    event: &HookEvent,
+    instance_id: &str,
+    policy: &PolicyMatch,
+    timeout_secs: u64,
+) -> PendingApproval {
+    let now = chrono::Local::now();
+    PendingApproval {
+        id: event
+            .event_id
+            .clone()
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+        created_at: now,
+        expires_at: now + chrono::Duration::seconds(timeout_secs as i64),
+        instance_id: instance_id.to_string(),
+        session_id: event.session_id.clone(),
+        cwd: event.cwd.clone(),
+        tool_name: event.tool_name.clone().unwrap_or_default(),
+        tool_input: event.tool_input.clone(),
+        rule_id: policy.rule_id.clone(),
+        reason: policy.reason.clone(),
+    }
+}
+
+fn decision_record(event: &HookEvent, instance_id: String, policy: &PolicyMatch) -> PolicyDecisionRecord {
+    PolicyDecisionRecord {
+        id: 0,
+        timestamp: chrono::Local::now(),
+        instance_id,
+        session_id: event.session_id.clone(),
+        cwd: event.cwd.clone(),
+        tool_name: event.tool_name.clone().unwrap_or_default(),
+        tool_input: event.tool_input.as_ref().map(|v| v.to_string()),
+        rule_id: policy.rule_id.clone(),
+        decision: policy.decision.as_str().to_string(),
+        reason: policy.reason.clone(),
+    }
 }
@@ -0,0 +1,115 @@
+use crate::database::Database;
+use crate::models::{HookEvent, HookEventName, SessionEvent, StoredSessionEvent};
+use chrono::{DateTime, Local};
+use uuid::Uuid;
+
+/// 将 hook 事件写入 sessions 表，返回实际落库的记录；重复事件返回 None
+pub fn ingest_hook_event(db: &Database, event: &HookEvent) -> rusqlite::Result<Option<StoredSessionEvent>> {
+    let instance_id = resolve_instance(db, event)?.unwrap_or_default();
+    if !instance_id.is_empty() {
+        db.set_instance_session(&instance_id, &event.session_id)?;
+    }
+    let session_event = to_session_event(event, instance_id);
+    Ok(db
+        .insert_session_event(&session_event)?
+        .map(|cursor| StoredSessionEvent {
+            cursor,
+            event: session_event,
+        }))
+}
+
+/// 依次按 pid、Claude session_id、工作目录匹配实例
+pub fn resolve_instance(db: &Database, event: &HookEvent) -> rusqlite::Result<Option<String>> {
+    if let Some(pid) = event.pid {
+        if let Some(id) = db.find_running_instance_by_pid(pid)? {
+            return Ok(Some(id));
+        }
+    }
+    if let Some(id) = db.find_instance_by_session(&event.session_id)? {
+        return Ok(Some(id));
+    }
+    if event.cwd.is_empty() {
+        return Ok(None);
+    }
+    db.find_running_instance_by_cwd(&event.cwd)
+}
+
+pub fn to_session_event(event: &HookEvent, instance_id: String) -> SessionEvent {
+    let timestamp = event
+        .timestamp_ms
+        .and_then(|ms| {
+            DateTime::from_timestamp(ms.div_euclid(1000), (ms.rem_euclid(1000) * 1_000_000) as u32)
+        })
+        .map(|dt| dt.with_timezone(&Local))
+        .unwrap_or_else(Local::now);
+
+    SessionEvent {
+        id: event
+            .event_id
+            .clone()
+            .unwrap_or_else(|| Uuid::new_v4().to_string()),
+        instance_id,
+        event_type: event.hook_event_name.as_str().to_string(),
+        content: event_content(event),
+        timestamp,
+        metadata: event_metadata(event),
+        session_id: Some(event.session_id.clone()),
+        transcript_path: event.transcript_path.clone(),
+        tool_name: event.tool_name.clone(),
+        tool_input: event.tool_input.as_ref().map(|v| v.to_string()),
+        tool_response: event.tool_response.as_ref().map(|v| v.to_string()),
+    }
+}
+
+fn event_content(event: &HookEvent) -> String {
+    match event.hook_event_name {
+        HookEventName::UserPromptSubmit => event.prompt.clone().unwrap_or_default(),
+        HookEventName::Notification => event.message.clone().unwrap_or_default(),
+        HookEventName::PreToolUse | HookEventName::PostToolUse => {
+            let tool = event.tool_name.as_deref().unwrap_or_default();
+            match tool_summary(event.tool_input.as_ref()) {
+                Some(summary) => format!("{}: {}", tool, summary),
+                None => tool.to_string(),
+            }
+        }
+        HookEventName::SessionStart => event.source.clone().unwrap_or_default(),
+        HookEventName::SessionEnd => event.reason.clone().unwrap_or_default(),
+        HookEventName::PreCompact => event.trigger.clone().unwrap_or_default(),
+        HookEventName::Stop | HookEventName::SubagentStop => String::new(),
+    }
+}
+
+/// 从 tool_input 中挑出最能代表本次调用的字段
+fn tool_summary(input: Option<&serde_json::Value>) -> Option<String> {
+    let input = input?;
+    ["command", "file_path", "path", "pattern", "url", "description"]
+        .iter()
+        .find_map(|key| input.get(key).and_then(|v| v.as_str()))
+        .map(|s| s.to_string())
+}
+
+/// 不常用、没有独立列的字段放进 metadata
+fn event_metadata(event: &HookEvent) -> Option<String> {
+    let mut metadata = serde_json::Map::new();
+    if let Some(pid) = event.pid {
+        metadata.insert("pid".into(), pid.into());
+    }
+    if let Some(host) = &event.host {
+        metadata.insert("host".into(), host.clone().into());
+    }
+    if !event.cwd.is_empty() {
+        metadata.insert("cwd".into(), event.cwd.clone().into());
+    }
+    if let Some(active) = event.stop_hook_active {
+        metadata.insert("stop_hook_active".into(), active.into());
+    }
+    if let Some(instructions) = &event.custom_instructions {
+        metadata.insert("custom_instructions".into(), instructions.clone().into());
+    }
+
+    if metadata.is_empty() {
+        None
+    } else {
+        Some(serde_json::Value::Object(metadata).to_string())
+    }
+}
@@ -0,0 +1,192 @@
+//! 解析 Claude CLI 参数，得到模型、权限模式、print 模式等启动方式。
+
+use crate::models::LaunchOptions;
+
+/// `args` 为 claude 程序名之后的参数
+pub fn parse(args: &[String]) -> LaunchOptions {
+    let mut options = LaunchOptions::default();
+    let mut i = 0;
+    while i < args.len() {
+        let (flag, inline) = match args[i].split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag, Some(value.to_string())),
+            _ => (args[i].as_str(), None),
+        };
+        i += 1;
+
+        // 取紧跟着的值；`--flag=value` 形式优先
+        let mut value = |required: bool| -> Option<String> {
+            if inline.is_some() {
+                return inline.clone();
+            }
+            let next = args.get(i)?;
+            if !required && next.starts_with('-') {
+                return None;
+            }
+            i += 1;
+            Some(next.clone())
+        };
+
+        match flag {
+            "--model" => options.model = value(true),
+            "--permission-mode" => options.permission_mode = value(true),
+            "--dangerously-skip-permissions" => options.skip_permissions = true,
+            "-p" | "--print" => options.print_mode = true,
+            "-c" | "--continue" => options.resumed = true,
+            "-r" | "--resume" => {
+                options.resumed = true;
+                // 不带 id 时 Claude 会弹出会话选择
+                options.resume_session_id = value(false);
+            }
+            "--output-format" => options.output_format = value(true),
+            "--mcp-config" => options.mcp_config = value(true),
+            "--add-dir" => {
+                // 可一次跟多个目录，直到下一个选项
+                if let Some(dir) = inline {
+                    options.add_dirs.push(dir);
+                }
+                while let Some(dir) = args.get(i).filter(|a| !a.starts_with('-')) {
+                    options.add_dirs.push(dir.clone());
+                    i += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    options
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_line(line: &str) -> LaunchOptions {
+        let args: Vec<String> = line.split_whitespace().map(str::to_string).collect();
+        parse(&args)
+    }
+
+    fn dirs(dirs: &[&str]) -> Vec<String> {
+        dirs.iter().map(|d| d.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_flags() {
+        let cases = [
+            ("", LaunchOptions::default()),
+            (
+                "--model opus --permission-mode plan",
+                LaunchOptions {
+                    model: Some("opus".into()),
+                    permission_mode: Some("plan".into()),
+                    ..Default::default()
+                },
+            ),
+            (
+                "--model=sonnet --output-format=stream-json --mcp-config=mcp.json",
+                LaunchOptions {
+                    model: Some("sonnet".into()),
+                    output_format: Some("stream-json".into()),
+                    mcp_config: Some("mcp.json".into()),
+                    ..Default::default()
+                },
+            ),
+            (
+                "--dangerously-skip-permissions -p",
+                LaunchOptions {
+                    skip_permissions: true,
+                    print_mode: true,
+                    ..Default::default()
+                },
+            ),
+            (
+                "--print --output-format json",
+                LaunchOptions {
+                    print_mode: true,
+                    output_format: Some("json".into()),
+                    ..Default::default()
+                },
+            ),
+            (
+                "-c",
+                LaunchOptions {
+                    resumed: true,
+                    ..Default::default()
+                },
+            ),
+            (
+                "--continue --model opus",
+                LaunchOptions {
+                    resumed: true,
+                    model: Some("opus".into()),
+                    ..Default::default()
+                },
+            ),
+            (
+                "-r",
+                LaunchOptions {
+                    resumed: true,
+                    ..Default::default()
+                },
+            ),
+            (
+                "-r 2f1c9a3e --model opus",
+                LaunchOptions {
+                    resumed: true,
+                    resume_session_id: Some("2f1c9a3e".into()),
+                    model: Some("opus".into()),
+                    ..Default::default()
+                },
+            ),
+            (
+                "--resume -p",
+                LaunchOptions {
+                    resumed: true,
+                    print_mode: true,
+                    ..Default::default()
+                },
+            ),
+            (
+                "--resume=2f1c9a3e",
+                LaunchOptions {
+                    resumed: true,
+                    resume_session_id: Some("2f1c9a3e".into()),
+                    ..Default::default()
+                },
+            ),
+            (
+                "--add-dir ../lib ../docs --model opus",
+                LaunchOptions {
+                    add_dirs: dirs(&["../lib", "../docs"]),
+                    model: Some("opus".into()),
+                    ..Default::default()
+                },
+            ),
+            (
+                "--add-dir=../lib ../docs -p",
+                LaunchOptions {
+                    add_dirs: dirs(&["../lib", "../docs"]),
+                    print_mode: true,
+                    ..Default::default()
+                },
+            ),
+            (
+                "--add-dir ../lib --add-dir ../docs",
+                LaunchOptions {
+                    add_dirs: dirs(&["../lib", "../docs"]),
+                    ..Default::default()
+                },
+            ),
+            ("--verbose --unknown=1 fix the bug", LaunchOptions::default()),
+        ];
+
+        for (line, expected) in cases {
+            assert_eq!(parse_line(line), expected, "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn missing_required_value_at_end() {
+        assert_eq!(parse_line("--model").model, None);
+        // 必需的值即使以 - 开头也照取
+        assert_eq!(parse_line("--model -p").model.as_deref(), Some("-p"));
+    }
+}
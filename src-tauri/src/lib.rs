@@ -1,10 +1,27 @@
+mod alerts;
+mod api;
+mod approval;
+mod auth;
+mod cgroup;
 mod commands;
 mod config;
 mod database;
+mod detection;
+mod event_stream;
+mod events;
+mod hook_installer;
 mod hook_server;
+mod ingest;
 mod installer;
+mod launch_options;
+mod metrics;
 mod monitor;
 mod models;
+mod notifier;
+mod policy;
+mod process_tree;
+mod spool;
+mod termination;
 
 use std::sync::Arc;
 use tauri::Manager;
@@ -14,44 +31,209 @@ pub struct AppState {
     pub db: Arc<Mutex<database::Database>>,
     pub monitor: Arc<Mutex<monitor::ProcessMonitor>>,
     pub hook_server: Arc<Mutex<hook_server::HookServer>>,
+    pub approvals: Arc<approval::ApprovalQueue>,
+    pub policy: Arc<policy::PolicyStore>,
+    pub hook_auth: Arc<auth::AuthGuard>,
+    pub events: Arc<events::EventBus>,
+    pub metrics: Arc<metrics::Metrics>,
 }
 
 pub fn run() {
+    let db = Arc::new(Mutex::new(database::Database::new().expect("Failed to init DB")));
+    let approvals = Arc::new(approval::ApprovalQueue::new());
+    let policy = Arc::new(policy::PolicyStore::load());
+    let hook_auth = Arc::new(auth::AuthGuard::load());
+    let event_bus = Arc::new(events::EventBus::new());
+    let metrics = Arc::new(metrics::Metrics::new());
+
     tauri::Builder::default()
         .manage(AppState {
-            db: Arc::new(Mutex::new(database::Database::new().expect("Failed to init DB"))),
+            db: db.clone(),
             monitor: Arc::new(Mutex::new(monitor::ProcessMonitor::new())),
-            hook_server: Arc::new(Mutex::new(hook_server::HookServer::new(9876))),
+            hook_server: Arc::new(Mutex::new(hook_server::HookServer::new(
+                db,
+                approvals.clone(),
+                policy.clone(),
+                hook_auth.clone(),
+                event_bus.clone(),
+                metrics.clone(),
+            ))),
+            approvals,
+            policy,
+            hook_auth,
+            events: event_bus,
+            metrics,
         })
         .setup(|app| {
             let state = app.state::<AppState>();
 
-            // 启动时初始化数据库
+            // 启动时初始化数据库，并接续上次运行时记录的实例
             tauri::async_runtime::block_on(async {
                 let db = state.db.lock().await;
                 db.init().expect("Failed to init database");
+
+                let known = db.get_instances(true).unwrap_or_default();
+                let stale: Vec<String> = state
+                    .monitor
+                    .lock()
+                    .await
+                    .restore(known)
+                    .into_iter()
+                    .map(|i| i.id)
+                    .collect();
+                match db.reconcile_stale_instances(&stale) {
+                    Ok(0) => {}
+                    Ok(count) => eprintln!("Marked {} stale instances as exited", count),
+                    Err(e) => eprintln!("Failed to reconcile instances: {}", e),
+                }
+                if let Err(e) = db.resolve_stale_alerts() {
+                    eprintln!("Failed to resolve stale alerts: {}", e);
+                }
+            });
+
+            events::forward_to_tauri(app.handle(), state.events.clone());
+            notifier::spawn(state.events.clone());
+
+            // 注销、关机或 Ctrl+C 不会触发 RunEvent::Exit，同样要恢复挂起的实例
+            let suspended = tauri::async_runtime::block_on(async { state.monitor.lock().await.suspended_instances() });
+            install_panic_hook(suspended.clone(), state.db.clone());
+            let db = state.db.clone();
+            let handle = app.handle();
+            tauri::async_runtime::spawn(async move {
+                let signal = termination_signal().await;
+                cleanup_before_exit(&suspended, Some(&*db.lock().await), signal);
+                handle.exit(0);
             });
 
             // 启动 hook 服务
             let hook_server = state.hook_server.clone();
             tauri::async_runtime::spawn(async move {
-                let server = hook_server.lock().await;
-                server.start().await;
+                let mut server = hook_server.lock().await;
+                match server.start().await {
+                    Ok(status) => eprintln!("Hook server listening on 127.0.0.1:{}", status.port.unwrap_or_default()),
+                    Err(e) => eprintln!("Failed to start hook server: {}", e),
+                }
             });
 
             // 启动监控循环
             let monitor = state.monitor.clone();
             let db = state.db.clone();
+            let bus = state.events.clone();
+            let metrics = state.metrics.clone();
             tauri::async_runtime::spawn(async move {
                 let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(5));
+                let mut alert_engine = alerts::AlertEngine::new();
+                let mut cgroup_root = cgroup::CgroupRoot::new();
                 loop {
                     interval.tick().await;
+                    let config = config::AppConfig::load();
+                    // 创建委派 scope 可能要等几秒，不能持有 monitor 的锁
+                    let limits_root = if config.cgroup.enabled {
+                        cgroup_root.get().await
+                    } else {
+                        cgroup_root.reset_backoff();
+                        None
+                    };
                     let mut mon = monitor.lock().await;
-                    let instances = mon.scan_instances().await;
+                    let mut scan = mon.scan_instances().await;
+                    for e in mon.apply_resource_limits(&config.cgroup, limits_root.as_deref(), &mut scan) {
+                        eprintln!("Failed to apply resource limits: {}", e);
+                    }
+
+                    metrics.record_scan(&scan.instances);
 
                     let database = db.lock().await;
-                    for instance in instances {
+
+                    let mut last_hook_events = std::collections::HashMap::new();
+                    if alerts::AlertEngine::needs_hook_activity(&config.alert_rules) {
+                        for instance in &scan.instances {
+                            if let Ok(Some(time)) = database.last_session_event_time(&instance.id) {
+                                last_hook_events.insert(instance.id.clone(), time);
+                            }
+                        }
+                    }
+                    let alert_changes = alert_engine.evaluate(
+                        &config.alert_rules,
+                        &scan.instances,
+                        &last_hook_events,
+                        chrono::Local::now(),
+                    );
+
+                    for instance in scan.instances {
                         let _ = database.upsert_instance(&instance);
+
+                        if scan.appeared.contains(&instance.id) {
+                            bus.publish(events::MonitorEvent::InstanceAppeared(instance));
+                        }
+                    }
+
+                    // 实例行写入后再写采样，保证外键存在
+                    for resource in scan.resources {
+                        if let Err(e) = database.insert_resource(&resource) {
+                            eprintln!("Failed to record resource sample: {}", e);
+                        }
+                        bus.publish(events::MonitorEvent::ResourceSample(resource));
+                    }
+
+                    for mut instance in scan.exited {
+                        // 没有被 monitor 终止时，尝试用 SessionEnd hook 的原因
+                        if instance.exit_reason.is_none() {
+                            instance.exit_reason = database
+                                .find_session_end_reason(&instance.id)
+                                .ok()
+                                .flatten()
+                                .map(|reason| format!("session ended: {}", reason));
+                        }
+                        if let Err(e) = database.mark_instance_exited(&instance) {
+                            eprintln!("Failed to record instance exit: {}", e);
+                        }
+                        bus.publish(events::MonitorEvent::InstanceExited {
+                            instance_id: instance.id,
+                            pid: instance.pid,
+                            cwd: instance.cwd,
+                            exit_reason: instance.exit_reason,
+                            runtime_secs: instance.runtime_secs,
+                        });
+                    }
+
+                    for orphan in scan.orphaned {
+                        eprintln!(
+                            "Process {} ({}) kept running after instance {} exited",
+                            orphan.process.pid, orphan.process.name, orphan.instance_id
+                        );
+                        bus.publish(events::MonitorEvent::OrphanDetected(orphan));
+                    }
+
+                    // 实例状态写入后再处理告警，挂起动作的状态不会被本次扫描覆盖
+                    for change in alert_changes {
+                        match change {
+                            alerts::AlertChange::Fired(alert, action) => {
+                                eprintln!("Alert {} on instance {}: {}", alert.rule_id, alert.pid, alert.message);
+                                if let Err(e) = database.upsert_alert(&alert) {
+                                    eprintln!("Failed to record alert: {}", e);
+                                }
+                                match alerts::run_action(&alert, action, &mut mon, &database, &bus) {
+                                    Ok(Some(plan)) => {
+                                        tauri::async_runtime::spawn(termination::terminate(
+                                            plan,
+                                            config.termination.clone(),
+                                            db.clone(),
+                                            bus.clone(),
+                                            monitor.clone(),
+                                        ));
+                                    }
+                                    Ok(None) => {}
+                                    Err(e) => eprintln!("Failed to run alert action {}: {}", action.as_str(), e),
+                                }
+                                bus.publish(events::MonitorEvent::AlertFired(alert));
+                            }
+                            alerts::AlertChange::Resolved(alert) => {
+                                if let Err(e) = database.upsert_alert(&alert) {
+                                    eprintln!("Failed to record alert: {}", e);
+                                }
+                                bus.publish(events::MonitorEvent::AlertResolved(alert));
+                            }
+                        }
                     }
                 }
             });
@@ -67,15 +249,98 @@ pub fn run() {
             commands::get_instance_resources,
             commands::get_instance_sessions,
             commands::kill_instance,
-            commands::get_hook_script,
+            commands::get_lifecycle_events,
+            commands::suspend_instance,
+            commands::resume_instance,
+            commands::get_instance_children,
+            commands::get_orphan_processes,
+            commands::get_alerts,
             commands::install_hook,
             commands::uninstall_hook,
             commands::get_workspace_stats,
             commands::search_history,
             commands::get_config,
             commands::save_config,
+            commands::send_test_notification,
             commands::is_hook_installed,
+            commands::get_policy_decisions,
+            commands::list_pending_approvals,
+            commands::approve_tool_call,
+            commands::deny_tool_call,
+            commands::get_rejected_hook_requests,
+            commands::get_hook_server_status,
+            commands::subscribe_events,
+            commands::unsubscribe_events,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                let state = app.state::<AppState>();
+                tauri::async_runtime::block_on(async {
+                    let suspended = state.monitor.lock().await.suspended_instances();
+                    cleanup_before_exit(&suspended, Some(&*state.db.lock().await), "exit");
+                });
+            }
+        });
+}
+
+/// 退出前恢复所有挂起的实例，并删除 hook 发现文件
+fn cleanup_before_exit(suspended: &monitor::SuspendedInstances, db: Option<&database::Database>, reason: &str) {
+    resume_suspended(suspended, db, reason);
+    hook_server::remove_discovery();
+}
+
+/// 恢复所有挂起的实例，避免留下冻结的进程；拿不到数据库时只恢复进程，下次扫描或启动时再更新状态
+fn resume_suspended(suspended: &monitor::SuspendedInstances, db: Option<&database::Database>, reason: &str) {
+    let resumed = suspended.resume_all();
+    if resumed.is_empty() {
+        return;
+    }
+    eprintln!("Resumed {} suspended instances ({})", resumed.len(), reason);
+    if let Some(db) = db {
+        let detail = format!("monitor {}", reason);
+        for instance_id in &resumed {
+            let result = db
+                .set_instance_status(instance_id, "running")
+                .and_then(|_| db.insert_lifecycle_event(instance_id, "resumed", Some(&detail)));
+            if let Err(e) = result {
+                eprintln!("Failed to record resume of {}: {}", instance_id, e);
+            }
+        }
+    }
+}
+
+/// 任何线程 panic 都先恢复挂起的实例：监控循环、信号处理和 hook 服务都跑在 tokio 工作线程上，
+/// 它们 panic 后不会再有人恢复这些进程。恢复可重复执行，应用继续运行时也不受影响；
+/// hook 发现文件只在正常退出时删除，panic 后残留的文件只会让 ccm-hook 连接失败、改写 spool
+fn install_panic_hook(suspended: monitor::SuspendedInstances, db: Arc<Mutex<database::Database>>) {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        // panic 时锁可能仍被持有，不能等待
+        resume_suspended(&suspended, db.try_lock().ok().as_deref(), "panic");
+        default_hook(info);
+    }));
+}
+
+#[cfg(unix)]
+async fn termination_signal() -> &'static str {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let (Ok(mut term), Ok(mut int)) = (signal(SignalKind::terminate()), signal(SignalKind::interrupt())) else {
+        eprintln!("Failed to install signal handlers");
+        return std::future::pending().await;
+    };
+    tokio::select! {
+        _ = term.recv() => "SIGTERM",
+        _ = int.recv() => "SIGINT",
+    }
+}
+
+#[cfg(not(unix))]
+async fn termination_signal() -> &'static str {
+    if tokio::signal::ctrl_c().await.is_err() {
+        return std::future::pending().await;
+    }
+    "Ctrl+C"
 }
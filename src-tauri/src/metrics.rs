@@ -0,0 +1,141 @@
+//! Prometheus 指标，`GET /metrics` 以文本格式输出。
+
+use crate::models::ClaudeInstance;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// 数据库写入耗时直方图的桶上限（秒）
+const LATENCY_BUCKETS: [f64; 10] = [0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 1.0];
+
+#[derive(Default)]
+struct Histogram {
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+#[derive(Default)]
+pub struct Metrics {
+    /// 最近一次扫描到的实例
+    instances: Mutex<Vec<ClaudeInstance>>,
+    hook_events: Mutex<BTreeMap<String, u64>>,
+    ingest_errors: AtomicU64,
+    db_write: Mutex<Histogram>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_scan(&self, instances: &[ClaudeInstance]) {
+        *self.instances.lock().unwrap() = instances.to_vec();
+    }
+
+    pub fn record_hook_event(&self, event_type: &str) {
+        *self
+            .hook_events
+            .lock()
+            .unwrap()
+            .entry(event_type.to_string())
+            .or_default() += 1;
+    }
+
+    pub fn record_ingest_error(&self) {
+        self.ingest_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_db_write(&self, elapsed: Duration) {
+        let secs = elapsed.as_secs_f64();
+        let mut histogram = self.db_write.lock().unwrap();
+        histogram.count += 1;
+        histogram.sum += secs;
+        for (bucket, le) in histogram.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if secs <= le {
+                *bucket += 1;
+            }
+        }
+    }
+
+    /// Prometheus 文本格式 (version 0.0.4)
+    pub fn render(&self, rejected_requests: u64) -> String {
+        let mut out = String::new();
+        let instances = self.instances.lock().unwrap().clone();
+
+        header(&mut out, "ccm_instances_running", "gauge", "Number of running Claude Code instances");
+        let _ = writeln!(out, "ccm_instances_running {}", instances.len());
+
+        header(&mut out, "ccm_instance_cpu_percent", "gauge", "CPU usage of a Claude Code instance");
+        for instance in &instances {
+            let _ = writeln!(out, "ccm_instance_cpu_percent{{{}}} {}", instance_labels(instance), instance.cpu_percent);
+        }
+
+        header(&mut out, "ccm_instance_memory_bytes", "gauge", "Resident memory of a Claude Code instance");
+        for instance in &instances {
+            let _ = writeln!(
+                out,
+                "ccm_instance_memory_bytes{{{}}} {}",
+                instance_labels(instance),
+                (instance.memory_mb * 1024.0 * 1024.0).round()
+            );
+        }
+
+        header(&mut out, "ccm_instance_uptime_seconds", "gauge", "Seconds since the instance process started");
+        let now = chrono::Local::now();
+        for instance in &instances {
+            let _ = writeln!(
+                out,
+                "ccm_instance_uptime_seconds{{{}}} {}",
+                instance_labels(instance),
+                (now - instance.start_time).num_seconds().max(0)
+            );
+        }
+
+        header(&mut out, "ccm_hook_events_total", "counter", "Hook events received by type");
+        for (event_type, count) in self.hook_events.lock().unwrap().iter() {
+            let _ = writeln!(out, "ccm_hook_events_total{{event_type=\"{}\"}} {}", escape(event_type), count);
+        }
+
+        header(&mut out, "ccm_hook_ingest_errors_total", "counter", "Hook events that failed to be stored");
+        let _ = writeln!(out, "ccm_hook_ingest_errors_total {}", self.ingest_errors.load(Ordering::Relaxed));
+
+        header(&mut out, "ccm_hook_rejected_requests_total", "counter", "Hook server requests rejected for a missing or wrong secret");
+        let _ = writeln!(out, "ccm_hook_rejected_requests_total {}", rejected_requests);
+
+        header(&mut out, "ccm_db_write_duration_seconds", "histogram", "Latency of storing a hook event");
+        let histogram = self.db_write.lock().unwrap();
+        for (le, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets) {
+            let _ = writeln!(out, "ccm_db_write_duration_seconds_bucket{{le=\"{}\"}} {}", le, count);
+        }
+        let _ = writeln!(out, "ccm_db_write_duration_seconds_bucket{{le=\"+Inf\"}} {}", histogram.count);
+        let _ = writeln!(out, "ccm_db_write_duration_seconds_sum {}", histogram.sum);
+        let _ = writeln!(out, "ccm_db_write_duration_seconds_count {}", histogram.count);
+
+        out
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn instance_labels(instance: &ClaudeInstance) -> String {
+    format!(
+        "instance_id=\"{}\",workspace=\"{}\",pid=\"{}\"",
+        escape(&instance.id),
+        escape(&instance.cwd),
+        instance.pid
+    )
+}
+
+/// 标签值中的反斜杠、双引号和换行需要转义
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -12,6 +12,132 @@ pub struct ClaudeInstance {
     pub last_seen: DateTime<Local>,
     pub cpu_percent: f32,
     pub memory_mb: f64,
+    /// 以下字段在实例退出后才有值
+    #[serde(default)]
+    pub end_time: Option<DateTime<Local>>,
+    #[serde(default)]
+    pub exit_reason: Option<String>,
+    #[serde(default)]
+    pub runtime_secs: Option<i64>,
+    /// hook 最近上报的 Claude session_id
+    #[serde(default)]
+    pub session_id: Option<String>,
+    /// 实例及其全部子孙进程的合计占用
+    #[serde(default)]
+    pub tree_cpu_percent: f32,
+    #[serde(default)]
+    pub tree_memory_mb: f64,
+    #[serde(default)]
+    pub child_count: u32,
+    /// 只在内存中保存，不入库
+    #[serde(default)]
+    pub children: Vec<ChildProcess>,
+    #[serde(default, flatten)]
+    pub launch: LaunchOptions,
+}
+
+/// 实例生命周期中由监控发起的操作记录，如终止
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LifecycleEvent {
+    pub id: i64,
+    pub instance_id: String,
+    pub timestamp: DateTime<Local>,
+    pub action: String,
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TerminationStatus {
+    SignalSent,
+    Exited,
+    Failed,
+}
+
+/// 终止实例过程中每一步的进度
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerminationProgress {
+    pub instance_id: String,
+    pub pid: u32,
+    /// 当前阶段的信号，如 SIGINT
+    pub signal: String,
+    pub status: TerminationStatus,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertStatus {
+    Firing,
+    Resolved,
+}
+
+impl AlertStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertStatus::Firing => "firing",
+            AlertStatus::Resolved => "resolved",
+        }
+    }
+}
+
+/// 告警规则在某个实例上的一次触发
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Alert {
+    pub id: String,
+    pub rule_id: String,
+    pub instance_id: String,
+    pub pid: u32,
+    pub cwd: String,
+    pub metric: String,
+    /// 触发期间的峰值
+    pub value: f64,
+    pub threshold: f64,
+    pub action: String,
+    pub status: AlertStatus,
+    pub message: String,
+    pub fired_at: DateTime<Local>,
+    pub resolved_at: Option<DateTime<Local>>,
+}
+
+/// 从 Claude 命令行参数解析出的启动方式
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LaunchOptions {
+    pub model: Option<String>,
+    pub permission_mode: Option<String>,
+    /// --dangerously-skip-permissions
+    pub skip_permissions: bool,
+    /// -p / --print
+    pub print_mode: bool,
+    /// --resume 或 --continue 接续了之前的会话
+    pub resumed: bool,
+    /// --resume 指定的 session id
+    pub resume_session_id: Option<String>,
+    pub output_format: Option<String>,
+    pub add_dirs: Vec<String>,
+    pub mcp_config: Option<String>,
+}
+
+/// Claude 实例派生的子孙进程，如 Bash 工具、测试、dev server 和 MCP server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChildProcess {
+    pub pid: u32,
+    pub parent_pid: Option<u32>,
+    pub name: String,
+    pub cmdline: String,
+    pub start_time: DateTime<Local>,
+    pub cpu_percent: f32,
+    pub memory_mb: f64,
+}
+
+/// 所属 Claude 实例退出后仍在运行的子进程
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrphanProcess {
+    pub instance_id: String,
+    pub cwd: String,
+    pub detected_at: DateTime<Local>,
+    #[serde(flatten)]
+    pub process: ChildProcess,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,6 +148,44 @@ pub struct InstanceResource {
     pub memory_mb: f64,
     pub disk_read_mb: u64,
     pub disk_write_mb: u64,
+    /// 距上次采样读写的字节数
+    #[serde(default)]
+    pub disk_read_bytes: u64,
+    #[serde(default)]
+    pub disk_write_bytes: u64,
+    /// 目前只有 Linux 能取到线程数和打开的文件描述符数
+    #[serde(default)]
+    pub thread_count: Option<u32>,
+    #[serde(default)]
+    pub fd_count: Option<u32>,
+    #[serde(default)]
+    pub tree_cpu_percent: f32,
+    #[serde(default)]
+    pub tree_memory_mb: f64,
+    #[serde(default)]
+    pub child_count: u32,
+    /// 实例所在 cgroup 的用量和节流统计，未启用资源限制时为空
+    #[serde(default)]
+    pub cgroup: Option<CgroupStats>,
+}
+
+/// 从 cgroup v2 接口文件读取的统计；限额为 None 表示不限
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CgroupStats {
+    /// cgroup 目录名，按工作区共享时多个实例相同
+    pub name: String,
+    pub cpu_usage_usec: u64,
+    /// 被节流的周期数和累计时长
+    pub nr_throttled: u64,
+    pub throttled_usec: u64,
+    /// cpu.max 折算的百分比，100 表示一个核
+    pub cpu_max_percent: Option<f64>,
+    pub memory_bytes: u64,
+    pub memory_max_bytes: Option<u64>,
+    /// 超过 memory.max 后被 OOM killer 结束的进程数
+    pub oom_kills: u64,
+    pub pids: u64,
+    pub pids_max: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -32,6 +196,19 @@ pub struct SessionEvent {
     pub content: String,
     pub timestamp: DateTime<Local>,
     pub metadata: Option<String>,
+    pub session_id: Option<String>,
+    pub transcript_path: Option<String>,
+    pub tool_name: Option<String>,
+    pub tool_input: Option<String>,
+    pub tool_response: Option<String>,
+}
+
+/// 已入库的会话事件，cursor 为 sessions 表的 rowid，单调递增，可作为断点续传的游标
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredSessionEvent {
+    pub cursor: i64,
+    #[serde(flatten)]
+    pub event: SessionEvent,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -43,15 +220,137 @@ pub struct WorkspaceStats {
     pub last_active: Option<DateTime<Local>>,
 }
 
+/// Claude Code 官方 hook 事件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum HookEventName {
+    PreToolUse,
+    PostToolUse,
+    UserPromptSubmit,
+    Notification,
+    Stop,
+    SubagentStop,
+    PreCompact,
+    SessionStart,
+    SessionEnd,
+}
+
+impl HookEventName {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HookEventName::PreToolUse => "PreToolUse",
+            HookEventName::PostToolUse => "PostToolUse",
+            HookEventName::UserPromptSubmit => "UserPromptSubmit",
+            HookEventName::Notification => "Notification",
+            HookEventName::Stop => "Stop",
+            HookEventName::SubagentStop => "SubagentStop",
+            HookEventName::PreCompact => "PreCompact",
+            HookEventName::SessionStart => "SessionStart",
+            HookEventName::SessionEnd => "SessionEnd",
+        }
+    }
+}
+
+/// Claude Code 通过 stdin 传给 hook 命令的 JSON
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HookEvent {
-    pub event: String,
-    pub pid: u32,
+    pub hook_event_name: HookEventName,
+    #[serde(default)]
+    pub event_id: Option<String>,
+    pub session_id: String,
+    #[serde(default)]
+    pub transcript_path: Option<String>,
+    #[serde(default)]
     pub cwd: String,
-    pub timestamp: i64,
-    pub data: Option<serde_json::Value>,
+    #[serde(default)]
+    pub pid: Option<u32>,
+    #[serde(default)]
+    pub timestamp_ms: Option<i64>,
+    #[serde(default)]
+    pub host: Option<String>,
+    #[serde(default)]
+    pub tool_name: Option<String>,
+    #[serde(default)]
+    pub tool_input: Option<serde_json::Value>,
+    #[serde(default)]
+    pub tool_response: Option<serde_json::Value>,
+    #[serde(default)]
+    pub prompt: Option<String>,
+    #[serde(default)]
+    pub message: Option<String>,
+    #[serde(default)]
+    pub stop_hook_active: Option<bool>,
+    #[serde(default)]
+    pub trigger: Option<String>,
+    #[serde(default)]
+    pub custom_instructions: Option<String>,
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub reason: Option<String>,
 }
 
+impl HookEvent {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.session_id.trim().is_empty() {
+            return Err("session_id is required".to_string());
+        }
+
+        let name = self.hook_event_name.as_str();
+        match self.hook_event_name {
+            HookEventName::PreToolUse | HookEventName::PostToolUse => {
+                if self.tool_name.as_deref().unwrap_or_default().is_empty() {
+                    return Err(format!("{} requires tool_name", name));
+                }
+                if self.tool_input.is_none() {
+                    return Err(format!("{} requires tool_input", name));
+                }
+                if self.hook_event_name == HookEventName::PostToolUse && self.tool_response.is_none() {
+                    return Err(format!("{} requires tool_response", name));
+                }
+            }
+            HookEventName::UserPromptSubmit if self.prompt.is_none() => {
+                return Err(format!("{} requires prompt", name));
+            }
+            HookEventName::Notification if self.message.is_none() => {
+                return Err(format!("{} requires message", name));
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyDecisionRecord {
+    pub id: i64,
+    pub timestamp: DateTime<Local>,
+    pub instance_id: String,
+    pub session_id: String,
+    pub cwd: String,
+    pub tool_name: String,
+    pub tool_input: Option<String>,
+    pub rule_id: String,
+    pub decision: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingApproval {
+    pub id: String,
+    pub created_at: DateTime<Local>,
+    pub expires_at: DateTime<Local>,
+    pub instance_id: String,
+    pub session_id: String,
+    pub cwd: String,
+    pub tool_name: String,
+    pub tool_input: Option<serde_json::Value>,
+    pub rule_id: String,
+    pub reason: String,
+}
+
+pub use ccm_hook::HookServerStatus;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InstallationStatus {
     pub installed: bool,
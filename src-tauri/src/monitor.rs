@@ -1,110 +1,483 @@
-use crate::models::ClaudeInstance;
+use crate::cgroup::{CgroupConfig, CgroupManager, LimitScope};
+use crate::detection::{self, ProcessRole};
+use crate::launch_options;
+use crate::models::{ChildProcess, ClaudeInstance, InstanceResource, OrphanProcess};
+use crate::process_tree::{self, ProcessInfo, ProcessTree};
+use crate::termination::{self, Target, TerminationPlan};
 use chrono::Local;
-use std::collections::HashMap;
-use sysinfo::{Process, System};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use sysinfo::{Pid, Signal, System};
 use uuid::Uuid;
 
+/// 一次扫描的结果，附带与上次扫描相比新出现和已退出的实例
+pub struct ScanResult {
+    pub instances: Vec<ClaudeInstance>,
+    /// 每个实例本次的资源采样
+    pub resources: Vec<InstanceResource>,
+    pub appeared: Vec<String>,
+    pub exited: Vec<ClaudeInstance>,
+    /// 本次新发现的孤儿进程
+    pub orphaned: Vec<OrphanProcess>,
+}
+
+/// 实例 id 的 UUIDv5 命名空间
+const INSTANCE_NAMESPACE: Uuid = Uuid::from_u128(0x6c1f_3e2a_9b4d_4f7e_8a15_c0de_c1a0_de01);
+
 pub struct ProcessMonitor {
+    /// 只由 scan_instances 刷新，CPU 和磁盘 IO 的增量都以一个扫描周期为准
     system: System,
+    /// 终止、挂起等操作按需查询进程树时使用，不影响扫描的周期增量
+    lookup: System,
+    /// 本次开机的标识，与 pid、进程启动时间一起确定实例 id
+    boot_id: String,
     instance_map: HashMap<u32, String>,
+    /// 上次扫描到的实例，用于在进程退出后仍能报告其信息
+    last_instances: HashMap<u32, ClaudeInstance>,
+    /// 由 monitor 主动终止的进程，退出时据此记录退出原因
+    killed: HashSet<u32>,
+    /// 所属实例已退出但仍在运行的子进程
+    orphans: HashMap<u32, OrphanProcess>,
+    /// 由 monitor 挂起的实例
+    suspended: SuspendedInstances,
+    cgroups: CgroupManager,
+    /// cgroup 名 -> 最近一次报告的放置错误，避免每次扫描重复输出；放置成功或 cgroup 不再使用时移除
+    cgroup_errors: HashMap<String, String>,
 }
 
 impl ProcessMonitor {
     pub fn new() -> Self {
         Self {
             system: System::new_all(),
+            lookup: System::new(),
+            boot_id: boot_id(),
             instance_map: HashMap::new(),
+            last_instances: HashMap::new(),
+            killed: HashSet::new(),
+            orphans: HashMap::new(),
+            suspended: SuspendedInstances::default(),
+            cgroups: CgroupManager::new(),
+            cgroup_errors: HashMap::new(),
         }
     }
 
-    pub async fn scan_instances(&mut self) -> Vec<ClaudeInstance> {
+    /// 同一进程在 monitor 重启后得到相同的 id；pid 被复用时启动时间不同，id 也不同
+    pub fn instance_id(&self, pid: u32, start_time: u64) -> String {
+        let key = format!("{}:{}:{}", self.boot_id, pid, start_time);
+        Uuid::new_v5(&INSTANCE_NAMESPACE, key.as_bytes()).to_string()
+    }
+
+    /// 启动时载入数据库中仍标记为运行或挂起的实例：进程还在的继续沿用原记录（不再触发 appeared），
+    /// 挂起的一并恢复；返回进程已不存在的实例
+    pub fn restore(&mut self, known: Vec<ClaudeInstance>) -> Vec<ClaudeInstance> {
+        self.lookup.refresh_processes();
+
+        let mut stale = Vec::new();
+        let mut was_suspended = Vec::new();
+        for instance in known {
+            let alive = self
+                .lookup
+                .process(Pid::from_u32(instance.pid))
+                .is_some_and(|p| self.instance_id(instance.pid, p.start_time()) == instance.id);
+            if alive {
+                if instance.status == "suspended" {
+                    was_suspended.push(instance.id.clone());
+                }
+                self.instance_map.insert(instance.pid, instance.id.clone());
+                self.last_instances.insert(instance.pid, instance);
+            } else {
+                stale.push(instance);
+            }
+        }
+
+        // 上次运行时挂起、没来得及恢复（如监控崩溃）的实例
+        for id in was_suspended {
+            if let Ok((pid, targets, process_group)) = self.instance_targets(&id) {
+                resume(&Suspension {
+                    instance_id: id,
+                    process_group,
+                    targets,
+                });
+                self.set_status(pid, "running");
+            }
+        }
+        stale
+    }
+
+    pub async fn scan_instances(&mut self) -> ScanResult {
         self.system.refresh_all();
+        let tree = ProcessTree::new(process_tree::snapshot(&self.system));
+        let now = Local::now();
+
+        // 孤儿进程退出后不再跟踪，仍在运行的更新占用
+        self.orphans.retain(|pid, orphan| {
+            match tree.get(*pid) {
+                Some(p) if p.start_time as i64 == orphan.process.start_time.timestamp() => {
+                    orphan.process.cpu_percent = p.cpu_percent;
+                    orphan.process.memory_mb = p.memory_mb;
+                    orphan.process.parent_pid = p.parent;
+                    true
+                }
+                _ => false,
+            }
+        });
+
+        // 上次扫描的实例已退出，但其子进程还在
+        let mut orphaned = Vec::new();
+        for instance in self.last_instances.values() {
+            if tree.is_alive(instance.pid, instance.start_time.timestamp() as u64) {
+                continue;
+            }
+            for child in &instance.children {
+                let Some(process) = tree.get(child.pid) else { continue };
+                if process.start_time as i64 != child.start_time.timestamp() || self.orphans.contains_key(&child.pid) {
+                    continue;
+                }
+                let orphan = OrphanProcess {
+                    instance_id: instance.id.clone(),
+                    cwd: instance.cwd.clone(),
+                    detected_at: now,
+                    process: process.to_child(),
+                };
+                self.orphans.insert(child.pid, orphan.clone());
+                orphaned.push(orphan);
+            }
+        }
+
+        // 孤儿进程即使本身是 claude 也不再单独算作实例
+        let roots = detection::detect(&tree, std::process::id())
+            .into_iter()
+            .filter(|pid| !self.orphans.contains_key(pid));
 
         let mut instances = Vec::new();
+        let mut resources = Vec::new();
+        let mut appeared = Vec::new();
 
-        for (pid, process) in self.system.processes() {
-            let name = process.name().to_lowercase();
-            let cmdline = process.cmd().join(" ");
-
-            // 检测 claude 进程
-            if self.is_claude_process(&name, &cmdline) {
-                let pid_u32 = pid.as_u32();
-
-                // 获取或生成实例 ID
-                let instance_id = self
-                    .instance_map
-                    .entry(pid_u32)
-                    .or_insert_with(|| Uuid::new_v4().to_string())
-                    .clone();
-
-                let cwd = process.cwd().map(|p| p.to_string_lossy().to_string())
-                    .unwrap_or_default();
-
-                let start_time = process.start_time();
-                let start_datetime = chrono::DateTime::from_timestamp(start_time as i64, 0)
-                    .map(|dt| dt.with_timezone(&Local))
-                    .unwrap_or_else(|| Local::now());
-
-                let cpu_usage = process.cpu_usage();
-                let memory_mb = (process.memory() as f64) / 1024.0 / 1024.0;
-
-                instances.push(ClaudeInstance {
-                    id: instance_id,
-                    pid: pid_u32,
-                    cwd,
-                    cmdline: cmdline.clone(),
-                    status: "running".to_string(),
-                    start_time: start_datetime,
-                    last_seen: Local::now(),
-                    cpu_percent: cpu_usage,
-                    memory_mb,
-                });
+        for pid_u32 in roots {
+            let (Some(info), Some(process)) = (tree.get(pid_u32), self.system.process(Pid::from_u32(pid_u32))) else {
+                continue;
+            };
+            let start_time = info.start_time;
+
+            // 实例 ID 由 pid 和启动时间决定；pid 被新进程复用时 id 不同，按新实例处理
+            let instance_id = self.instance_id(pid_u32, start_time);
+            if self.instance_map.get(&pid_u32) != Some(&instance_id) {
+                self.instance_map.insert(pid_u32, instance_id.clone());
+                appeared.push(instance_id.clone());
             }
+
+            let cwd = process.cwd().map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_default();
+
+            let start_datetime = chrono::DateTime::from_timestamp(start_time as i64, 0)
+                .map(|dt| dt.with_timezone(&Local))
+                .unwrap_or_else(|| Local::now());
+
+            let cpu_usage = info.cpu_percent;
+            let memory_mb = info.memory_mb;
+
+            let children: Vec<ChildProcess> = tree.descendants(pid_u32).into_iter().map(ProcessInfo::to_child).collect();
+            let tree_cpu_percent = cpu_usage + children.iter().map(|c| c.cpu_percent).sum::<f32>();
+            let tree_memory_mb = memory_mb + children.iter().map(|c| c.memory_mb).sum::<f64>();
+            let child_count = children.len() as u32;
+
+            // self.system 只在每个周期开始时 refresh_all，read_bytes / written_bytes 即本周期的增量
+            let disk = process.disk_usage();
+            resources.push(InstanceResource {
+                instance_id: instance_id.clone(),
+                timestamp: now,
+                cpu_percent: cpu_usage,
+                memory_mb,
+                disk_read_mb: disk.read_bytes / 1024 / 1024,
+                disk_write_mb: disk.written_bytes / 1024 / 1024,
+                disk_read_bytes: disk.read_bytes,
+                disk_write_bytes: disk.written_bytes,
+                thread_count: process.tasks().map(|tasks| tasks.len() as u32),
+                fd_count: fd_count(pid_u32),
+                tree_cpu_percent,
+                tree_memory_mb,
+                child_count,
+                cgroup: None,
+            });
+
+            instances.push(ClaudeInstance {
+                id: instance_id,
+                pid: pid_u32,
+                cwd,
+                cmdline: info.cmdline(),
+                status: if self.suspended.lock().contains_key(&pid_u32) { "suspended" } else { "running" }.to_string(),
+                start_time: start_datetime,
+                last_seen: now,
+                cpu_percent: cpu_usage,
+                memory_mb,
+                end_time: None,
+                exit_reason: None,
+                runtime_secs: None,
+                session_id: None,
+                tree_cpu_percent,
+                tree_memory_mb,
+                child_count,
+                children,
+                launch: launch_options::parse(detection::claude_arguments(info)),
+            });
         }
 
         // 清理已不存在的进程
         let active_pids: Vec<u32> = instances.iter().map(|i| i.pid).collect();
+        let active_ids: HashSet<&str> = instances.iter().map(|i| i.id.as_str()).collect();
         self.instance_map
             .retain(|pid, _| active_pids.contains(pid));
 
-        instances
+        let current: HashMap<u32, ClaudeInstance> =
+            instances.iter().map(|i| (i.pid, i.clone())).collect();
+        let exited = std::mem::replace(&mut self.last_instances, current)
+            .into_values()
+            .filter(|i| !active_ids.contains(i.id.as_str()))
+            .map(|mut instance| {
+                instance.status = "exited".to_string();
+                instance.end_time = Some(now);
+                instance.runtime_secs = Some((now - instance.start_time).num_seconds().max(0));
+                if self.killed.remove(&instance.pid) {
+                    instance.exit_reason = Some("terminated from monitor".to_string());
+                }
+                instance.children.clear();
+                instance
+            })
+            .collect();
+        self.killed.retain(|pid| active_pids.contains(pid));
+        self.suspended.lock().retain(|_, s| active_ids.contains(s.instance_id.as_str()));
+
+        ScanResult {
+            instances,
+            resources,
+            appeared,
+            exited,
+            orphaned,
+        }
+    }
+
+    /// 按配置把本次扫描到的实例放进 cgroup 并更新限额，把 cgroup 统计填入资源采样；
+    /// 返回新出现的错误
+    /// `root` 为 CgroupRoot 准备好的委派子树，尚不可用时跳过
+    pub fn apply_resource_limits(&mut self, config: &CgroupConfig, root: Option<&Path>, scan: &mut ScanResult) -> Vec<String> {
+        if !config.enabled {
+            self.cgroups.release_all();
+            self.cgroup_errors.clear();
+            return Vec::new();
+        }
+        let Some(root) = root else {
+            return Vec::new();
+        };
+
+        let mut errors = Vec::new();
+        let mut active = HashSet::new();
+        let mut failed = HashSet::new();
+        for instance in &scan.instances {
+            let name = match config.scope {
+                LimitScope::Instance => format!("instance-{}", instance.id),
+                LimitScope::Workspace => {
+                    format!("workspace-{}", Uuid::new_v5(&Uuid::NAMESPACE_URL, instance.cwd.as_bytes()).simple())
+                }
+            };
+            let pids: Vec<u32> = std::iter::once(instance.pid)
+                .chain(instance.children.iter().map(|c| c.pid))
+                .collect();
+            if let Err(e) = self.cgroups.place(root, &name, &pids, config.limits_for(&instance.cwd)) {
+                if self.cgroup_errors.get(&name) != Some(&e) {
+                    errors.push(e.clone());
+                    self.cgroup_errors.insert(name.clone(), e);
+                }
+                failed.insert(name);
+                continue;
+            }
+            if let Some(resource) = scan.resources.iter_mut().find(|r| r.instance_id == instance.id) {
+                resource.cgroup = self.cgroups.stats(&name);
+            }
+            active.insert(name);
+        }
+        self.cgroups.remove_unused(&active);
+
+        // 同一工作区的实例共用 cgroup，其中一个放置成功不代表错误已解决
+        self.cgroup_errors.retain(|name, _| failed.contains(name));
+        errors
     }
 
-    fn is_claude_process(&self, name: &str, cmdline: &str) -> bool {
-        // Windows: node.exe running claude
-        // macOS/Linux: claude or node with claude
-        let indicators = [
-            "claude",
-            "@anthropic-ai/claude-code",
-            "claude-code",
-        ];
+    /// 实例最近一次扫描到的子孙进程
+    pub fn children_of(&self, instance_id: &str) -> Option<Vec<ChildProcess>> {
+        self.last_instances
+            .values()
+            .find(|i| i.id == instance_id)
+            .map(|i| i.children.clone())
+    }
 
-        let is_node = name.contains("node") || name.contains("npm");
-        let is_claude = name.contains("claude");
+    /// 所属实例已退出但仍在运行的进程
+    pub fn orphans(&self) -> Vec<OrphanProcess> {
+        let mut orphans: Vec<OrphanProcess> = self.orphans.values().cloned().collect();
+        orphans.sort_by_key(|o| o.process.pid);
+        orphans
+    }
 
-        if is_claude {
-            return true;
+    /// 准备终止实例：校验 id 对应的仍是同一个进程，收集整棵进程树和可用的进程组
+    pub fn termination_plan(&mut self, instance_id: &str) -> Result<TerminationPlan, String> {
+        let (pid, targets, process_group) = self.instance_targets(instance_id)?;
+        // 停止状态的进程收不到 SIGINT / SIGTERM，先恢复
+        let suspension = self.suspended.lock().remove(&pid);
+        if let Some(suspension) = suspension {
+            resume(&suspension);
         }
+        // 保留 instance_map 中的记录，下次扫描时按退出处理
+        self.killed.insert(pid);
+        Ok(TerminationPlan {
+            instance_id: instance_id.to_string(),
+            pid,
+            targets,
+            process_group,
+        })
+    }
 
-        if is_node {
-            return indicators.iter().any(|ind| cmdline.to_lowercase().contains(ind));
+    /// 用 SIGSTOP 冻结实例及其整棵进程树
+    pub fn suspend_instance(&mut self, instance_id: &str) -> Result<u32, String> {
+        let (pid, targets, process_group) = self.instance_targets(instance_id)?;
+        if self.suspended.lock().contains_key(&pid) {
+            return Err(format!("Instance already suspended: {}", instance_id));
+        }
+        // 先停父进程，避免它在子进程停下期间再派生新进程
+        if !termination::send_signal(process_group, &targets, Signal::Stop) {
+            return Err("Suspending processes is not supported on this platform".to_string());
         }
+        self.suspended.lock().insert(
+            pid,
+            Suspension {
+                instance_id: instance_id.to_string(),
+                process_group,
+                targets,
+            },
+        );
+        self.set_status(pid, "suspended");
+        Ok(pid)
+    }
 
-        false
+    /// 用 SIGCONT 恢复挂起的实例；挂起期间不会产生新进程，按挂起时的进程树恢复
+    pub fn resume_instance(&mut self, instance_id: &str) -> Result<u32, String> {
+        let pid = self
+            .last_instances
+            .values()
+            .find(|i| i.id == instance_id)
+            .map(|i| i.pid)
+            .ok_or_else(|| format!("Instance not running: {}", instance_id))?;
+        let suspension = self
+            .suspended
+            .lock()
+            .remove(&pid)
+            .ok_or_else(|| format!("Instance is not suspended: {}", instance_id))?;
+        resume(&suspension);
+        self.set_status(pid, "running");
+        Ok(pid)
     }
 
-    pub fn kill_process(&mut self, pid: u32) -> Result<(), String> {
-        self.system.refresh_all();
+    /// 挂起实例的共享句柄，退出、收到终止信号或 panic 时不经过 monitor 的锁也能恢复
+    pub fn suspended_instances(&self) -> SuspendedInstances {
+        self.suspended.clone()
+    }
 
-        if let Some(process) = self.system.process((pid as usize).into()) {
-            if process.kill() {
-                self.instance_map.remove(&pid);
-                Ok(())
-            } else {
-                Err("Failed to kill process".to_string())
-            }
-        } else {
-            Err("Process not found".to_string())
+    fn set_status(&mut self, pid: u32, status: &str) {
+        if let Some(instance) = self.last_instances.get_mut(&pid) {
+            instance.status = status.to_string();
         }
     }
+
+    /// 校验 id 对应的仍是同一个进程，返回实例 pid、整棵进程树和可整组发送信号的进程组
+    fn instance_targets(&mut self, instance_id: &str) -> Result<(u32, Vec<Target>, Option<u32>), String> {
+        let pid = self
+            .last_instances
+            .values()
+            .find(|i| i.id == instance_id)
+            .map(|i| i.pid)
+            .ok_or_else(|| format!("Instance not running: {}", instance_id))?;
+
+        self.lookup.refresh_processes();
+        let tree = ProcessTree::new(process_tree::snapshot(&self.lookup));
+        let info = tree
+            .get(pid)
+            .filter(|p| self.instance_id(pid, p.start_time) == instance_id)
+            .ok_or_else(|| format!("Instance already exited: {}", instance_id))?;
+
+        let targets = std::iter::once(info)
+            .chain(tree.descendants(pid))
+            .map(|p| Target { pid: p.pid, start_time: p.start_time })
+            .collect();
+
+        // 只有组长是实例自己或其包装进程时才整组发送信号，避免波及启动它的 shell 或监控自己
+        let wrappers: Vec<u32> = tree
+            .ancestors(pid)
+            .into_iter()
+            .take_while(|a| tree.get(*a).and_then(detection::classify) == Some(ProcessRole::Wrapper))
+            .collect();
+        let own_group = termination::process_group(std::process::id());
+        let process_group = termination::process_group(pid)
+            .filter(|group| (*group == pid || wrappers.contains(group)) && Some(*group) != own_group);
+
+        Ok((pid, targets, process_group))
+    }
+
+    pub fn cancel_termination(&mut self, pid: u32) {
+        self.killed.remove(&pid);
+    }
+}
+
+/// 挂起时的进程树，恢复时原样发送 SIGCONT
+struct Suspension {
+    instance_id: String,
+    process_group: Option<u32>,
+    targets: Vec<Target>,
+}
+
+/// pid -> 挂起时的进程树；单独加锁，panic 时 monitor 的锁可能还被持有
+#[derive(Clone, Default)]
+pub struct SuspendedInstances(Arc<Mutex<HashMap<u32, Suspension>>>);
+
+impl SuspendedInstances {
+    fn lock(&self) -> MutexGuard<'_, HashMap<u32, Suspension>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// 恢复所有挂起的实例，返回它们的实例 id
+    pub fn resume_all(&self) -> Vec<String> {
+        let suspended = std::mem::take(&mut *self.lock());
+        suspended
+            .into_values()
+            .map(|suspension| {
+                resume(&suspension);
+                suspension.instance_id
+            })
+            .collect()
+    }
+}
+
+/// 子进程先恢复，父进程最后，父进程恢复后看到的子进程都已在运行
+fn resume(suspension: &Suspension) {
+    let targets: Vec<Target> = suspension.targets.iter().rev().copied().collect();
+    termination::send_signal(suspension.process_group, &targets, Signal::Continue);
+}
+
+/// Linux 使用内核的 boot_id，其他平台用开机时间代替
+fn boot_id() -> String {
+    #[cfg(target_os = "linux")]
+    if let Ok(id) = std::fs::read_to_string("/proc/sys/kernel/random/boot_id") {
+        return id.trim().to_string();
+    }
+    System::boot_time().to_string()
+}
+
+#[cfg(target_os = "linux")]
+fn fd_count(pid: u32) -> Option<u32> {
+    std::fs::read_dir(format!("/proc/{}/fd", pid))
+        .ok()
+        .map(|entries| entries.count() as u32)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn fd_count(_pid: u32) -> Option<u32> {
+    None
 }
@@ -0,0 +1,850 @@
+//! 通知：任务完成（Stop）、等待输入（Notification hook）和告警事件按路由规则
+//! 发送到桌面通知、webhook、SMTP 邮件或自定义命令，静默时段内只发送标记为忽略静默的路由。
+
+use crate::config::AppConfig;
+use crate::events::{EventBus, MonitorEvent};
+use async_trait::async_trait;
+use chrono::{DateTime, Local, NaiveTime};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::broadcast;
+
+/// 与 tauri.conf.json 的 bundle.identifier 一致
+const APP_IDENTIFIER: &str = "com.claudemonitor.app";
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    /// Claude 回复结束（Stop hook）
+    TaskCompleted,
+    /// Claude 等待用户输入或授权（Notification hook）
+    InputNeeded,
+    AlertFired,
+    AlertResolved,
+    /// 在界面上手动发送的测试通知
+    Test,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Notification {
+    pub kind: NotificationKind,
+    pub title: String,
+    pub body: String,
+    pub instance_id: String,
+    pub session_id: Option<String>,
+    pub cwd: String,
+    pub timestamp: DateTime<Local>,
+}
+
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    async fn send(&self, notification: &Notification) -> Result<(), String>;
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NotificationConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub channels: Vec<ChannelConfig>,
+    /// 通知发往所有命中路由的渠道之和
+    #[serde(default)]
+    pub routes: Vec<NotificationRoute>,
+    #[serde(default)]
+    pub quiet_hours: Option<QuietHours>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChannelConfig {
+    pub id: String,
+    #[serde(flatten)]
+    pub backend: Backend,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Backend {
+    Desktop,
+    Webhook(WebhookConfig),
+    Smtp(SmtpConfig),
+    Command(CommandConfig),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    pub url: String,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// JSON 模板，字符串中的 `{{title}}`、`{{body}}` 等占位符替换为通知字段；为空时发送默认格式
+    #[serde(default)]
+    pub template: Option<Value>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SmtpSecurity {
+    #[default]
+    Starttls,
+    Tls,
+    /// 明文，仅用于本机中继
+    None,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmtpConfig {
+    pub host: String,
+    #[serde(default = "default_smtp_port")]
+    pub port: u16,
+    #[serde(default)]
+    pub security: SmtpSecurity,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    pub from: String,
+    pub to: Vec<String>,
+}
+
+fn default_smtp_port() -> u16 {
+    587
+}
+
+/// 参数支持与 webhook 模板相同的占位符，通知的 JSON 写入标准输入
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandConfig {
+    pub program: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationRoute {
+    /// 为空表示所有事件
+    #[serde(default)]
+    pub events: Vec<NotificationKind>,
+    /// cwd 位于该目录下才生效；为空表示全局
+    #[serde(default)]
+    pub workspace: Option<String>,
+    pub channels: Vec<String>,
+    /// 静默时段内照常发送
+    #[serde(default)]
+    pub ignore_quiet_hours: bool,
+}
+
+/// 本地时间 `HH:MM`，start 晚于 end 时跨越午夜
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuietHours {
+    pub start: String,
+    pub end: String,
+}
+
+impl QuietHours {
+    fn contains(&self, time: NaiveTime) -> Result<bool, String> {
+        let start = parse_time(&self.start)?;
+        let end = parse_time(&self.end)?;
+        Ok(if start <= end {
+            time >= start && time < end
+        } else {
+            time >= start || time < end
+        })
+    }
+}
+
+fn parse_time(value: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(value, "%H:%M").map_err(|_| format!("Invalid time {:?}, expected HH:MM", value))
+}
+
+impl NotificationConfig {
+    /// 保存配置前检查渠道和路由
+    pub fn validate(&self) -> Result<(), String> {
+        let mut ids = HashSet::new();
+        for channel in &self.channels {
+            if channel.id.is_empty() || !ids.insert(channel.id.as_str()) {
+                return Err(format!("Notification channel id must be unique and non-empty: {:?}", channel.id));
+            }
+            match &channel.backend {
+                Backend::Webhook(webhook) => {
+                    reqwest::Url::parse(&webhook.url)
+                        .map_err(|e| format!("Channel {}: invalid url: {}", channel.id, e))?;
+                }
+                Backend::Smtp(smtp) if smtp.to.is_empty() => {
+                    return Err(format!("Channel {}: no recipients", channel.id));
+                }
+                _ => {}
+            }
+        }
+        for route in &self.routes {
+            if let Some(unknown) = route.channels.iter().find(|c| !ids.contains(c.as_str())) {
+                return Err(format!("Notification route refers to unknown channel {}", unknown));
+            }
+        }
+        if let Some(quiet) = &self.quiet_hours {
+            quiet.contains(NaiveTime::MIN)?;
+        }
+        Ok(())
+    }
+
+    /// 通知应发往的渠道
+    pub fn channels_for(&self, notification: &Notification, time: NaiveTime) -> Vec<&ChannelConfig> {
+        let quiet = self
+            .quiet_hours
+            .as_ref()
+            .is_some_and(|q| q.contains(time).unwrap_or(false));
+        let ids: HashSet<&str> = self
+            .routes
+            .iter()
+            .filter(|route| route.events.is_empty() || route.events.contains(&notification.kind))
+            .filter(|route| match &route.workspace {
+                Some(workspace) => Path::new(&notification.cwd).starts_with(workspace),
+                None => true,
+            })
+            .filter(|route| !quiet || route.ignore_quiet_hours)
+            .flat_map(|route| route.channels.iter().map(String::as_str))
+            .collect();
+        self.channels.iter().filter(|c| ids.contains(c.id.as_str())).collect()
+    }
+}
+
+pub fn build(backend: &Backend) -> Box<dyn Notifier> {
+    match backend {
+        Backend::Desktop => Box::new(DesktopNotifier),
+        Backend::Webhook(config) => Box::new(WebhookNotifier::new(config.clone())),
+        Backend::Smtp(config) => Box::new(SmtpNotifier(config.clone())),
+        Backend::Command(config) => Box::new(CommandNotifier(config.clone())),
+    }
+}
+
+pub struct DesktopNotifier;
+
+#[async_trait]
+impl Notifier for DesktopNotifier {
+    async fn send(&self, notification: &Notification) -> Result<(), String> {
+        tauri::api::notification::Notification::new(APP_IDENTIFIER)
+            .title(&notification.title)
+            .body(&notification.body)
+            .show()
+            .map_err(|e| format!("Failed to show desktop notification: {}", e))
+    }
+}
+
+/// 默认格式同时带 `text`（Slack、Teams）和 `title` / `message`（Gotify、ntfy）
+pub struct WebhookNotifier {
+    config: WebhookConfig,
+    client: reqwest::Client,
+}
+
+impl WebhookNotifier {
+    pub fn new(config: WebhookConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(SEND_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        Self { config, client }
+    }
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    async fn send(&self, notification: &Notification) -> Result<(), String> {
+        let fields = fields(notification);
+        let payload = match &self.config.template {
+            Some(template) => render(template, &fields),
+            None => serde_json::json!({
+                "text": format!("{}\n{}", notification.title, notification.body),
+                "title": notification.title,
+                "message": notification.body,
+                "kind": notification.kind,
+                "instance_id": notification.instance_id,
+                "session_id": notification.session_id,
+                "cwd": notification.cwd,
+                "timestamp": notification.timestamp,
+            }),
+        };
+
+        let mut request = self.client.post(&self.config.url).json(&payload);
+        for (name, value) in &self.config.headers {
+            request = request.header(name, value);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("Webhook request failed: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!("Webhook returned {}", response.status()));
+        }
+        Ok(())
+    }
+}
+
+pub struct SmtpNotifier(SmtpConfig);
+
+#[async_trait]
+impl Notifier for SmtpNotifier {
+    async fn send(&self, notification: &Notification) -> Result<(), String> {
+        use lettre::message::header::ContentType;
+        use lettre::transport::smtp::authentication::Credentials;
+        use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+        let config = &self.0;
+        let builder = match config.security {
+            SmtpSecurity::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host),
+            SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host),
+            SmtpSecurity::None => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host)),
+        }
+        .map_err(|e| format!("Invalid SMTP host {}: {}", config.host, e))?;
+        let mut builder = builder.port(config.port).timeout(Some(SEND_TIMEOUT));
+        if let (Some(username), Some(password)) = (&config.username, &config.password) {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+
+        let mut message = Message::builder()
+            .from(config.from.parse().map_err(|e| format!("Invalid sender {}: {}", config.from, e))?)
+            .subject(&notification.title)
+            .header(ContentType::TEXT_PLAIN);
+        for to in &config.to {
+            message = message.to(to.parse().map_err(|e| format!("Invalid recipient {}: {}", to, e))?);
+        }
+        let body = format!("{}\n\n{}\n{}", notification.body, notification.cwd, notification.timestamp.to_rfc3339());
+        let email = message.body(body).map_err(|e| format!("Failed to build email: {}", e))?;
+
+        builder
+            .build()
+            .send(email)
+            .await
+            .map(|_| ())
+            .map_err(|e| format!("SMTP delivery failed: {}", e))
+    }
+}
+
+pub struct CommandNotifier(CommandConfig);
+
+#[async_trait]
+impl Notifier for CommandNotifier {
+    async fn send(&self, notification: &Notification) -> Result<(), String> {
+        let fields = fields(notification);
+        let mut child = tokio::process::Command::new(&self.0.program)
+            .args(self.0.args.iter().map(|arg| substitute(arg, &fields)))
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("Failed to run {}: {}", self.0.program, e))?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            let json = serde_json::to_vec(notification).unwrap_or_default();
+            // 命令可能不读标准输入
+            let _ = stdin.write_all(&json).await;
+        }
+        let output = tokio::time::timeout(COMMAND_TIMEOUT, child.wait_with_output())
+            .await
+            .map_err(|_| format!("{} timed out", self.0.program))?
+            .map_err(|e| format!("Failed to wait for {}: {}", self.0.program, e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "{} exited with {}: {}",
+                self.0.program,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// 模板可用的字段
+fn fields(notification: &Notification) -> Map<String, Value> {
+    match serde_json::to_value(notification) {
+        Ok(Value::Object(map)) => map,
+        _ => Map::new(),
+    }
+}
+
+fn render(template: &Value, fields: &Map<String, Value>) -> Value {
+    match template {
+        Value::String(s) => Value::String(substitute(s, fields)),
+        Value::Array(items) => Value::Array(items.iter().map(|item| render(item, fields)).collect()),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| (key.clone(), render(value, fields)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// 单次扫描替换 `{{key}}`，替换进来的值不会再被展开；未知字段原样保留
+fn substitute(text: &str, fields: &Map<String, Value>) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let placeholder = after
+            .find("}}")
+            .and_then(|end| fields.get(&after[..end]).map(|value| (value, end)));
+        match placeholder {
+            Some((value, end)) => {
+                match value {
+                    Value::String(s) => result.push_str(s),
+                    Value::Null => {}
+                    other => result.push_str(&other.to_string()),
+                }
+                rest = &after[end + 2..];
+            }
+            // 只跳过一个 `{`，`{{{cwd}}}` 中的 `{{cwd}}` 仍会被替换
+            None => {
+                result.push('{');
+                rest = &rest[start + 1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// 需要通知的事件
+pub fn from_event(event: &MonitorEvent) -> Option<Notification> {
+    match event {
+        MonitorEvent::HookEventReceived(stored) => {
+            let event = &stored.event;
+            let metadata: Option<Value> = event.metadata.as_deref().and_then(|m| serde_json::from_str(m).ok());
+            let metadata_field = |key: &str| metadata.as_ref().and_then(|m| m.get(key).cloned());
+            let cwd = metadata_field("cwd")
+                .and_then(|v| v.as_str().map(str::to_string))
+                .unwrap_or_default();
+            let (kind, title, body) = match event.event_type.as_str() {
+                // stop hook 让 Claude 继续工作时任务还没结束
+                "Stop" if metadata_field("stop_hook_active") != Some(Value::Bool(true)) => (
+                    NotificationKind::TaskCompleted,
+                    "Claude Code 任务完成".to_string(),
+                    workspace_name(&cwd),
+                ),
+                "Notification" => (
+                    NotificationKind::InputNeeded,
+                    format!("Claude Code 等待输入 · {}", workspace_name(&cwd)),
+                    event.content.clone(),
+                ),
+                _ => return None,
+            };
+            Some(Notification {
+                kind,
+                title,
+                body,
+                instance_id: event.instance_id.clone(),
+                session_id: event.session_id.clone(),
+                cwd,
+                timestamp: event.timestamp,
+            })
+        }
+        MonitorEvent::AlertFired(alert) | MonitorEvent::AlertResolved(alert) => {
+            let (kind, prefix, timestamp) = match alert.resolved_at {
+                Some(resolved_at) => (NotificationKind::AlertResolved, "告警解除", resolved_at),
+                None => (NotificationKind::AlertFired, "告警", alert.fired_at),
+            };
+            Some(Notification {
+                kind,
+                title: format!("{}：{} · {}", prefix, alert.rule_id, workspace_name(&alert.cwd)),
+                body: alert.message.clone(),
+                instance_id: alert.instance_id.clone(),
+                session_id: None,
+                cwd: alert.cwd.clone(),
+                timestamp,
+            })
+        }
+        _ => None,
+    }
+}
+
+fn workspace_name(cwd: &str) -> String {
+    Path::new(cwd)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| cwd.to_string())
+}
+
+/// 订阅事件总线，按当前配置发送通知；每条通知各自在后台发送，慢的渠道不阻塞其他通知
+pub fn spawn(bus: Arc<EventBus>) {
+    let mut receiver = bus.subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let envelope = match receiver.recv().await {
+                Ok(envelope) => envelope,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    eprintln!("Notifier skipped {} events", skipped);
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            let Some(notification) = from_event(&envelope.event) else {
+                continue;
+            };
+            let config = AppConfig::load().notifications;
+            if !config.enabled {
+                continue;
+            }
+            for channel in config.channels_for(&notification, Local::now().time()) {
+                let channel = channel.clone();
+                let notification = notification.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = build(&channel.backend).send(&notification).await {
+                        eprintln!("Notification channel {} failed: {}", channel.id, e);
+                    }
+                });
+            }
+        }
+    });
+}
+
+/// 向指定渠道发送一条测试通知，不受路由和静默时段影响
+pub async fn send_test(config: &NotificationConfig, channel_id: &str) -> Result<(), String> {
+    let channel = config
+        .channels
+        .iter()
+        .find(|c| c.id == channel_id)
+        .ok_or_else(|| format!("Unknown notification channel: {}", channel_id))?;
+    let notification = Notification {
+        kind: NotificationKind::Test,
+        title: "Claude Code Monitor 测试通知".to_string(),
+        body: format!("渠道 {} 配置正常", channel.id),
+        instance_id: String::new(),
+        session_id: None,
+        cwd: String::new(),
+        timestamp: Local::now(),
+    };
+    build(&channel.backend).send(&notification).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+    use tokio::sync::oneshot;
+
+    fn notification(kind: NotificationKind, cwd: &str) -> Notification {
+        Notification {
+            kind,
+            title: "Claude Code 任务完成".to_string(),
+            body: "app".to_string(),
+            instance_id: "inst-1".to_string(),
+            session_id: Some("sess-1".to_string()),
+            cwd: cwd.to_string(),
+            timestamp: Local::now(),
+        }
+    }
+
+    fn channel(id: &str) -> ChannelConfig {
+        ChannelConfig { id: id.to_string(), backend: Backend::Desktop }
+    }
+
+    fn route(events: Vec<NotificationKind>, workspace: Option<&str>, channels: &[&str]) -> NotificationRoute {
+        NotificationRoute {
+            events,
+            workspace: workspace.map(str::to_string),
+            channels: channels.iter().map(|c| c.to_string()).collect(),
+            ignore_quiet_hours: false,
+        }
+    }
+
+    fn time(value: &str) -> NaiveTime {
+        parse_time(value).unwrap()
+    }
+
+    fn ids(channels: Vec<&ChannelConfig>) -> Vec<&str> {
+        channels.into_iter().map(|c| c.id.as_str()).collect()
+    }
+
+    /// 只响应一次的 HTTP 服务，返回收到的请求头和请求体
+    async fn http_stand_in(status: &'static str) -> (String, oneshot::Receiver<(String, Value)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let (tx, rx) = oneshot::channel();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut head = String::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).await.unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                head.push_str(&line);
+            }
+            let length: usize = head
+                .lines()
+                .find_map(|l| l.to_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse().unwrap()))
+                .unwrap();
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).await.unwrap();
+            let response = format!("HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+            reader.get_mut().write_all(response.as_bytes()).await.unwrap();
+            let _ = tx.send((head, serde_json::from_slice(&body).unwrap()));
+        });
+        (url, rx)
+    }
+
+    /// 最小的 SMTP 服务，返回 DATA 段内容
+    async fn smtp_stand_in() -> (u16, oneshot::Receiver<(Vec<String>, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (tx, rx) = oneshot::channel();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut reader = BufReader::new(stream);
+            reader.get_mut().write_all(b"220 localhost ESMTP\r\n").await.unwrap();
+            let mut commands = Vec::new();
+            let mut data = String::new();
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).await.unwrap() == 0 {
+                    break;
+                }
+                let command = line.trim_end().to_string();
+                let reply: &[u8] = match command.split(' ').next().unwrap_or_default().to_uppercase().as_str() {
+                    "EHLO" | "HELO" => b"250 localhost\r\n",
+                    "DATA" => {
+                        reader.get_mut().write_all(b"354 end with .\r\n").await.unwrap();
+                        loop {
+                            let mut line = String::new();
+                            reader.read_line(&mut line).await.unwrap();
+                            if line == ".\r\n" {
+                                break;
+                            }
+                            data.push_str(&line);
+                        }
+                        b"250 queued\r\n"
+                    }
+                    "QUIT" => {
+                        reader.get_mut().write_all(b"221 bye\r\n").await.unwrap();
+                        commands.push(command);
+                        break;
+                    }
+                    _ => b"250 OK\r\n",
+                };
+                commands.push(command);
+                reader.get_mut().write_all(reply).await.unwrap();
+            }
+            let _ = tx.send((commands, data));
+        });
+        (port, rx)
+    }
+
+    #[test]
+    fn routes_by_event_and_workspace() {
+        let config = NotificationConfig {
+            enabled: true,
+            channels: vec![channel("desktop"), channel("slack"), channel("mail")],
+            routes: vec![
+                route(vec![NotificationKind::TaskCompleted, NotificationKind::InputNeeded], None, &["desktop"]),
+                route(vec![], Some("/home/dev/app"), &["slack"]),
+                route(vec![NotificationKind::AlertFired], None, &["mail", "desktop"]),
+            ],
+            quiet_hours: None,
+        };
+        let noon = time("12:00");
+
+        let done = notification(NotificationKind::TaskCompleted, "/home/dev/app/web");
+        assert_eq!(ids(config.channels_for(&done, noon)), ["desktop", "slack"]);
+
+        let elsewhere = notification(NotificationKind::TaskCompleted, "/home/dev/other");
+        assert_eq!(ids(config.channels_for(&elsewhere, noon)), ["desktop"]);
+
+        let alert = notification(NotificationKind::AlertFired, "/tmp");
+        assert_eq!(ids(config.channels_for(&alert, noon)), ["desktop", "mail"]);
+
+        let resolved = notification(NotificationKind::AlertResolved, "/tmp");
+        assert!(config.channels_for(&resolved, noon).is_empty());
+    }
+
+    #[test]
+    fn quiet_hours_across_midnight() {
+        let mut urgent = route(vec![NotificationKind::AlertFired], None, &["phone"]);
+        urgent.ignore_quiet_hours = true;
+        let config = NotificationConfig {
+            enabled: true,
+            channels: vec![channel("desktop"), channel("phone")],
+            routes: vec![route(vec![], None, &["desktop"]), urgent],
+            quiet_hours: Some(QuietHours { start: "22:00".to_string(), end: "07:30".to_string() }),
+        };
+
+        let done = notification(NotificationKind::TaskCompleted, "/tmp");
+        assert!(config.channels_for(&done, time("23:15")).is_empty());
+        assert!(config.channels_for(&done, time("03:00")).is_empty());
+        assert_eq!(ids(config.channels_for(&done, time("07:30"))), ["desktop"]);
+        assert_eq!(ids(config.channels_for(&done, time("21:59"))), ["desktop"]);
+
+        let alert = notification(NotificationKind::AlertFired, "/tmp");
+        assert_eq!(ids(config.channels_for(&alert, time("02:00"))), ["phone"]);
+    }
+
+    #[test]
+    fn validates_routes_and_times() {
+        let mut config = NotificationConfig {
+            enabled: true,
+            channels: vec![channel("desktop")],
+            routes: vec![route(vec![], None, &["desktop"])],
+            quiet_hours: Some(QuietHours { start: "22:00".to_string(), end: "08:00".to_string() }),
+        };
+        assert!(config.validate().is_ok());
+
+        config.routes.push(route(vec![], None, &["pager"]));
+        assert!(config.validate().unwrap_err().contains("pager"));
+        config.routes.pop();
+
+        config.quiet_hours = Some(QuietHours { start: "10pm".to_string(), end: "08:00".to_string() });
+        assert!(config.validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn webhook_posts_default_payload() {
+        let (url, received) = http_stand_in("200 OK").await;
+        let notifier = WebhookNotifier::new(WebhookConfig {
+            url,
+            headers: HashMap::from([("Authorization".to_string(), "Bearer token".to_string())]),
+            template: None,
+        });
+        notifier
+            .send(&notification(NotificationKind::TaskCompleted, "/home/dev/app"))
+            .await
+            .unwrap();
+
+        let (head, body) = received.await.unwrap();
+        assert!(head.starts_with("POST /hook HTTP/1.1"));
+        assert!(head.to_lowercase().contains("authorization: bearer token"));
+        assert_eq!(body["text"], "Claude Code 任务完成\napp");
+        assert_eq!(body["kind"], "task_completed");
+        assert_eq!(body["session_id"], "sess-1");
+    }
+
+    #[tokio::test]
+    async fn webhook_renders_template() {
+        let (url, received) = http_stand_in("200 OK").await;
+        let notifier = WebhookNotifier::new(WebhookConfig {
+            url,
+            headers: HashMap::new(),
+            template: Some(serde_json::json!({
+                "topic": "claude",
+                "title": "{{title}}",
+                "message": "{{body}} ({{kind}}) in {{cwd}}",
+                "tags": ["{{instance_id}}"],
+                "priority": 4,
+            })),
+        });
+        notifier
+            .send(&notification(NotificationKind::InputNeeded, "/home/dev/app"))
+            .await
+            .unwrap();
+
+        let (_, body) = received.await.unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "topic": "claude",
+                "title": "Claude Code 任务完成",
+                "message": "app (input_needed) in /home/dev/app",
+                "tags": ["inst-1"],
+                "priority": 4,
+            })
+        );
+    }
+
+    #[test]
+    fn substitutes_placeholders_in_a_single_pass() {
+        let mut message = notification(NotificationKind::InputNeeded, "/home/dev/app");
+        message.title = "Claude needs {{title}}".to_string();
+        message.body = "literal {{cwd}} and {{body}}".to_string();
+        let fields = fields(&message);
+
+        assert_eq!(
+            substitute("{{title}}: {{body}} in {{cwd}}", &fields),
+            "Claude needs {{title}}: literal {{cwd}} and {{body}} in /home/dev/app"
+        );
+        assert_eq!(substitute("{{{cwd}}} {{unknown}} {{cwd", &fields), "{/home/dev/app} {{unknown}} {{cwd");
+        assert_eq!(substitute("{{kind}}/{{session_id}}", &fields), "input_needed/sess-1");
+    }
+
+    #[tokio::test]
+    async fn webhook_reports_error_status() {
+        let (url, _received) = http_stand_in("500 Internal Server Error").await;
+        let notifier = WebhookNotifier::new(WebhookConfig { url, headers: HashMap::new(), template: None });
+        let error = notifier
+            .send(&notification(NotificationKind::TaskCompleted, "/tmp"))
+            .await
+            .unwrap_err();
+        assert!(error.contains("500"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn smtp_delivers_message() {
+        let (port, received) = smtp_stand_in().await;
+        let notifier = SmtpNotifier(SmtpConfig {
+            host: "127.0.0.1".to_string(),
+            port,
+            security: SmtpSecurity::None,
+            username: None,
+            password: None,
+            from: "Monitor <monitor@example.com>".to_string(),
+            to: vec!["dev@example.com".to_string(), "ops@example.com".to_string()],
+        });
+        notifier
+            .send(&notification(NotificationKind::TaskCompleted, "/home/dev/app"))
+            .await
+            .unwrap();
+
+        let (commands, data) = received.await.unwrap();
+        assert!(commands.iter().any(|c| c == "MAIL FROM:<monitor@example.com>"));
+        assert!(commands.iter().any(|c| c == "RCPT TO:<dev@example.com>"));
+        assert!(commands.iter().any(|c| c == "RCPT TO:<ops@example.com>"));
+        assert!(data.contains("Subject: "));
+        assert!(data.contains("/home/dev/app"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn command_receives_placeholders_and_stdin() {
+        let dir = std::env::temp_dir().join(format!("ccm-notifier-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let out = dir.join("out.txt");
+        let notifier = CommandNotifier(CommandConfig {
+            program: "sh".to_string(),
+            args: vec![
+                "-c".to_string(),
+                format!("echo \"$0\" > {0}; cat >> {0}", out.display()),
+                "{{kind}}:{{cwd}}".to_string(),
+            ],
+        });
+        notifier
+            .send(&notification(NotificationKind::TaskCompleted, "/home/dev/app"))
+            .await
+            .unwrap();
+
+        let output = std::fs::read_to_string(&out).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        let (first, stdin) = output.split_once('\n').unwrap();
+        assert_eq!(first, "task_completed:/home/dev/app");
+        assert_eq!(serde_json::from_str::<Value>(stdin).unwrap()["instance_id"], "inst-1");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn command_reports_failure() {
+        let notifier = CommandNotifier(CommandConfig {
+            program: "sh".to_string(),
+            args: vec!["-c".to_string(), "echo boom >&2; exit 3".to_string()],
+        });
+        let error = notifier
+            .send(&notification(NotificationKind::TaskCompleted, "/tmp"))
+            .await
+            .unwrap_err();
+        assert!(error.contains("boom"), "{}", error);
+    }
+}
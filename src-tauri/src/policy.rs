@@ -0,0 +1,426 @@
+use crate::approval::ApprovalConfig;
+use crate::config::AppConfig;
+use crate::models::HookEvent;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+/// 工具调用中可能携带文件路径的字段
+const PATH_KEYS: [&str; 3] = ["file_path", "path", "notebook_path"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicyDecision {
+    Allow,
+    Deny,
+    Ask,
+    /// 挂起请求，交给 monitor 中的人工审批队列
+    Queue,
+}
+
+impl PolicyDecision {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PolicyDecision::Allow => "allow",
+            PolicyDecision::Deny => "deny",
+            PolicyDecision::Ask => "ask",
+            PolicyDecision::Queue => "queue",
+        }
+    }
+}
+
+/// PreToolUse 规则：所有已设置的条件都满足才算命中，按顺序取第一条命中的规则
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyRule {
+    pub id: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 规则所属工作区，cwd 位于该目录下才生效；为空表示全局
+    #[serde(default)]
+    pub workspace: Option<String>,
+    /// 工具名正则（整体匹配），如 `Bash`、`Edit|Write`
+    #[serde(default)]
+    pub tool_name: Option<String>,
+    /// Bash 命令正则
+    #[serde(default)]
+    pub command_regex: Option<String>,
+    /// 文件路径 glob，绝对路径或相对 cwd 的路径匹配其一即可
+    #[serde(default)]
+    pub path_globs: Vec<String>,
+    #[serde(default)]
+    pub cwd_glob: Option<String>,
+    /// 只在工具涉及 cwd 之外的路径时命中
+    #[serde(default)]
+    pub outside_cwd: bool,
+    pub decision: PolicyDecision,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Clone)]
+pub struct PolicyMatch {
+    pub rule_id: String,
+    pub decision: PolicyDecision,
+    pub reason: String,
+}
+
+/// 编译好的规则，正则和 glob 在加载配置时编译一次
+struct CompiledRule {
+    rule: PolicyRule,
+    tool_name: Option<Regex>,
+    command_regex: Option<Regex>,
+    path_globs: Vec<glob::Pattern>,
+    cwd_glob: Option<glob::Pattern>,
+}
+
+impl CompiledRule {
+    fn new(rule: &PolicyRule) -> Result<Self, String> {
+        let tool_name = rule
+            .tool_name
+            .as_deref()
+            .map(anchored)
+            .transpose()
+            .map_err(|e| format!("Rule {}: invalid tool_name: {}", rule.id, e))?;
+        let command_regex = rule
+            .command_regex
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .map_err(|e| format!("Rule {}: invalid command_regex: {}", rule.id, e))?;
+        let compile_glob = |pattern: &String| {
+            glob::Pattern::new(pattern).map_err(|e| format!("Rule {}: invalid glob: {}", rule.id, e))
+        };
+        Ok(Self {
+            tool_name,
+            command_regex,
+            path_globs: rule.path_globs.iter().map(compile_glob).collect::<Result<_, _>>()?,
+            cwd_glob: rule.cwd_glob.as_ref().map(compile_glob).transpose()?,
+            rule: rule.clone(),
+        })
+    }
+
+    fn matches(&self, event: &HookEvent) -> bool {
+        let cwd = Path::new(&event.cwd);
+
+        if let Some(workspace) = &self.rule.workspace {
+            if event.cwd.is_empty() || !cwd.starts_with(workspace) {
+                return false;
+            }
+        }
+
+        if let Some(pattern) = &self.cwd_glob {
+            if !pattern.matches_path(cwd) {
+                return false;
+            }
+        }
+
+        let tool = event.tool_name.as_deref().unwrap_or_default();
+        if let Some(re) = &self.tool_name {
+            if !re.is_match(tool) {
+                return false;
+            }
+        }
+
+        if let Some(re) = &self.command_regex {
+            if !tool_input_str(event, "command").is_some_and(|command| re.is_match(command)) {
+                return false;
+            }
+        }
+
+        let paths = referenced_paths(event, cwd);
+
+        if !self.path_globs.is_empty() {
+            let hit = paths.iter().any(|path| {
+                self.path_globs.iter().any(|pattern| {
+                    pattern.matches_path(path)
+                        || path.strip_prefix(cwd).is_ok_and(|rel| pattern.matches_path(rel))
+                })
+            });
+            if !hit {
+                return false;
+            }
+        }
+
+        if self.rule.outside_cwd && !paths.iter().any(|path| !path.starts_with(cwd)) {
+            return false;
+        }
+
+        true
+    }
+}
+
+#[derive(Default)]
+pub struct PolicyEngine {
+    rules: Vec<CompiledRule>,
+}
+
+impl PolicyEngine {
+    /// 编译所有启用的规则；不合法的规则被跳过，错误一并返回
+    pub fn new(rules: &[PolicyRule]) -> (Self, Vec<String>) {
+        let mut compiled = Vec::new();
+        let mut errors = Vec::new();
+        for rule in rules.iter().filter(|rule| rule.enabled) {
+            match CompiledRule::new(rule) {
+                Ok(rule) => compiled.push(rule),
+                Err(e) => errors.push(e),
+            }
+        }
+        (Self { rules: compiled }, errors)
+    }
+
+    /// 保存配置前检查正则和 glob 是否合法
+    pub fn validate_rules(rules: &[PolicyRule]) -> Result<(), String> {
+        for rule in rules {
+            CompiledRule::new(rule)?;
+        }
+        Ok(())
+    }
+
+    pub fn evaluate(&self, event: &HookEvent) -> Option<PolicyMatch> {
+        self.rules
+            .iter()
+            .find(|compiled| compiled.matches(event))
+            .map(|compiled| PolicyMatch {
+                rule_id: compiled.rule.id.clone(),
+                decision: compiled.rule.decision,
+                reason: compiled
+                    .rule
+                    .reason
+                    .clone()
+                    .unwrap_or_else(|| format!("Matched monitor policy rule {}", compiled.rule.id)),
+            })
+    }
+
+    /// Claude Code PreToolUse hook 的 JSON 输出，decision 不能是 Queue
+    pub fn hook_output(decision: PolicyDecision, reason: &str) -> serde_json::Value {
+        serde_json::json!({
+            "hookSpecificOutput": {
+                "hookEventName": "PreToolUse",
+                "permissionDecision": decision.as_str(),
+                "permissionDecisionReason": reason,
+            }
+        })
+    }
+}
+
+/// hook 服务当前使用的规则和审批配置，启动时和保存配置后重新加载
+#[derive(Default)]
+pub struct ActivePolicy {
+    pub engine: PolicyEngine,
+    pub approval: ApprovalConfig,
+}
+
+#[derive(Default)]
+pub struct PolicyStore {
+    active: RwLock<Arc<ActivePolicy>>,
+}
+
+impl PolicyStore {
+    pub fn load() -> Self {
+        let store = Self::default();
+        store.reload(&AppConfig::load());
+        store
+    }
+
+    pub fn reload(&self, config: &AppConfig) {
+        let (engine, errors) = PolicyEngine::new(&config.policy_rules);
+        for e in errors {
+            eprintln!("Skipping policy rule: {}", e);
+        }
+        *self.active.write().unwrap() = Arc::new(ActivePolicy {
+            engine,
+            approval: config.approval.clone(),
+        });
+    }
+
+    pub fn current(&self) -> Arc<ActivePolicy> {
+        self.active.read().unwrap().clone()
+    }
+}
+
+fn anchored(pattern: &str) -> Result<Regex, regex::Error> {
+    Regex::new(&format!("^(?:{})$", pattern))
+}
+
+fn tool_input_str<'a>(event: &'a HookEvent, key: &str) -> Option<&'a str> {
+    event.tool_input.as_ref()?.get(key)?.as_str()
+}
+
+/// 收集工具调用涉及的路径：文件类工具取 tool_input 中的路径字段，
+/// Bash 取命令里看起来像路径的参数，统一解析为绝对路径
+fn referenced_paths(event: &HookEvent, cwd: &Path) -> Vec<PathBuf> {
+    let mut raw: Vec<&str> = PATH_KEYS
+        .iter()
+        .filter_map(|key| tool_input_str(event, key))
+        .collect();
+
+    if let Some(command) = tool_input_str(event, "command") {
+        raw.extend(
+            command
+                .split_whitespace()
+                .map(|token| token.trim_matches(|c| c == '"' || c == '\'' || c == ';'))
+                .filter(|token| {
+                    token.starts_with('/') || token.starts_with('~') || token.contains("..")
+                }),
+        );
+    }
+
+    raw.into_iter().map(|path| resolve_path(path, cwd)).collect()
+}
+
+fn resolve_path(path: &str, cwd: &Path) -> PathBuf {
+    let path = match path.strip_prefix('~') {
+        Some(rest) => dirs::home_dir()
+            .unwrap_or_default()
+            .join(rest.trim_start_matches(['/', '\\'])),
+        None => cwd.join(path),
+    };
+
+    // 只做词法上的规范化，路径可能还不存在
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::CurDir => {}
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rules(value: serde_json::Value) -> Vec<PolicyRule> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn engine(value: serde_json::Value) -> PolicyEngine {
+        let (engine, errors) = PolicyEngine::new(&rules(value));
+        assert!(errors.is_empty(), "{:?}", errors);
+        engine
+    }
+
+    fn pre_tool_use(cwd: &str, tool: &str, input: serde_json::Value) -> HookEvent {
+        serde_json::from_value(json!({
+            "hook_event_name": "PreToolUse",
+            "session_id": "s1",
+            "cwd": cwd,
+            "tool_name": tool,
+            "tool_input": input,
+        }))
+        .unwrap()
+    }
+
+    fn bash(command: &str) -> HookEvent {
+        pre_tool_use("/work/app", "Bash", json!({ "command": command }))
+    }
+
+    fn matched(engine: &PolicyEngine, event: &HookEvent) -> Option<String> {
+        engine.evaluate(event).map(|m| m.rule_id)
+    }
+
+    #[test]
+    fn first_matching_enabled_rule_wins() {
+        let engine = engine(json!([
+            { "id": "disabled", "enabled": false, "tool_name": "Bash", "decision": "allow" },
+            { "id": "deny-rm", "tool_name": "Bash", "command_regex": "rm ", "decision": "deny" },
+            { "id": "ask-bash", "tool_name": "Bash", "decision": "ask" },
+            { "id": "deny-all", "decision": "deny" },
+        ]));
+
+        let hit = engine.evaluate(&bash("rm -rf build")).unwrap();
+        assert_eq!(hit.rule_id, "deny-rm");
+        assert_eq!(hit.decision, PolicyDecision::Deny);
+        assert_eq!(hit.reason, "Matched monitor policy rule deny-rm");
+        assert_eq!(matched(&engine, &bash("ls")).as_deref(), Some("ask-bash"));
+        assert_eq!(
+            matched(&engine, &pre_tool_use("/work/app", "Read", json!({ "file_path": "a" }))).as_deref(),
+            Some("deny-all")
+        );
+    }
+
+    #[test]
+    fn tool_name_is_anchored_but_command_regex_searches() {
+        let engine = engine(json!([
+            { "id": "edit", "tool_name": "Edit|Write", "decision": "deny" },
+            { "id": "curl", "command_regex": "curl", "decision": "ask" },
+            { "id": "git-push", "command_regex": "^git push", "decision": "deny" },
+        ]));
+
+        let edit = |tool| pre_tool_use("/work/app", tool, json!({ "file_path": "a.rs" }));
+        assert_eq!(matched(&engine, &edit("Edit")).as_deref(), Some("edit"));
+        assert_eq!(matched(&engine, &edit("Write")).as_deref(), Some("edit"));
+        assert_eq!(matched(&engine, &edit("MultiEdit")), None);
+        assert_eq!(matched(&engine, &edit("NotebookEdit")), None);
+
+        assert_eq!(matched(&engine, &bash("echo x | curl -d @- host")).as_deref(), Some("curl"));
+        assert_eq!(matched(&engine, &bash("git push origin main")).as_deref(), Some("git-push"));
+        assert_eq!(matched(&engine, &bash("echo git push")), None);
+        // 没有 command 字段的工具不会命中 command_regex
+        assert_eq!(matched(&engine, &edit("Read")), None);
+    }
+
+    #[test]
+    fn path_globs_match_absolute_or_cwd_relative() {
+        let engine = engine(json!([
+            { "id": "migrations", "path_globs": ["migrations/**"], "decision": "deny" },
+            { "id": "etc", "path_globs": ["/etc/*"], "decision": "deny" },
+        ]));
+
+        let write = |path| pre_tool_use("/work/app", "Write", json!({ "file_path": path }));
+        assert_eq!(matched(&engine, &write("migrations/001.sql")).as_deref(), Some("migrations"));
+        assert_eq!(matched(&engine, &write("/work/app/migrations/001.sql")).as_deref(), Some("migrations"));
+        assert_eq!(matched(&engine, &write("src/../migrations/001.sql")).as_deref(), Some("migrations"));
+        assert_eq!(matched(&engine, &write("/other/migrations/001.sql")), None);
+        assert_eq!(matched(&engine, &write("/etc/hosts")).as_deref(), Some("etc"));
+        assert_eq!(matched(&engine, &write("src/main.rs")), None);
+        assert_eq!(matched(&engine, &bash("cat /etc/passwd")).as_deref(), Some("etc"));
+    }
+
+    #[test]
+    fn bash_paths_are_split_on_whitespace() {
+        let paths = |command| referenced_paths(&bash(command), Path::new("/work/app"));
+
+        assert_eq!(paths("rm -rf /tmp/x build"), vec![PathBuf::from("/tmp/x")]);
+        assert_eq!(paths("cat \"/etc/hosts\"; ls"), vec![PathBuf::from("/etc/hosts")]);
+        assert_eq!(paths("cp a ../other/b"), vec![PathBuf::from("/work/other/b")]);
+        assert_eq!(paths("ls src"), Vec::<PathBuf>::new());
+        // 带空格的路径会被拆开，只剩以 / 开头的一段
+        assert_eq!(paths("ls '/work/app/my dir'"), vec![PathBuf::from("/work/app/my")]);
+    }
+
+    #[test]
+    fn outside_cwd_only_matches_paths_leaving_the_workspace() {
+        let engine = engine(json!([
+            { "id": "outside", "tool_name": "Bash", "command_regex": "rm\\s+-\\w*r", "outside_cwd": true, "decision": "deny" },
+        ]));
+
+        assert_eq!(matched(&engine, &bash("rm -rf /work/app/build")), None);
+        assert_eq!(matched(&engine, &bash("rm -rf build")), None);
+        assert_eq!(matched(&engine, &bash("rm -rf ../shared")).as_deref(), Some("outside"));
+        assert_eq!(matched(&engine, &bash("rm -r /var/data")).as_deref(), Some("outside"));
+    }
+
+    #[test]
+    fn invalid_rules_are_skipped_and_reported() {
+        let rules = rules(json!([
+            { "id": "bad", "command_regex": "(", "decision": "deny" },
+            { "id": "good", "tool_name": "Bash", "decision": "ask" },
+        ]));
+
+        assert!(PolicyEngine::validate_rules(&rules).unwrap_err().contains("bad"));
+        let (engine, errors) = PolicyEngine::new(&rules);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(matched(&engine, &bash("ls")).as_deref(), Some("good"));
+    }
+}
@@ -0,0 +1,132 @@
+//! 进程快照与父子关系，用于把 Claude 实例派生的子进程归到实例名下。
+
+use crate::models::ChildProcess;
+use chrono::Local;
+use std::collections::{HashMap, HashSet};
+use sysinfo::{Process, System};
+
+/// 一个进程在某次扫描时的状态
+#[derive(Debug, Clone)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub parent: Option<u32>,
+    pub name: String,
+    pub cmd: Vec<String>,
+    /// 进程启动时间（unix 秒），与 pid 一起区分被复用的 pid
+    pub start_time: u64,
+    pub cpu_percent: f32,
+    pub memory_mb: f64,
+}
+
+impl ProcessInfo {
+    pub fn from_process(process: &Process) -> Self {
+        Self {
+            pid: process.pid().as_u32(),
+            parent: process.parent().map(|p| p.as_u32()),
+            name: process.name().to_string(),
+            cmd: process.cmd().to_vec(),
+            start_time: process.start_time(),
+            cpu_percent: process.cpu_usage(),
+            memory_mb: (process.memory() as f64) / 1024.0 / 1024.0,
+        }
+    }
+
+    pub fn cmdline(&self) -> String {
+        self.cmd.join(" ")
+    }
+
+    pub fn to_child(&self) -> ChildProcess {
+        ChildProcess {
+            pid: self.pid,
+            parent_pid: self.parent,
+            name: self.name.clone(),
+            cmdline: self.cmdline(),
+            start_time: chrono::DateTime::from_timestamp(self.start_time as i64, 0)
+                .map(|dt| dt.with_timezone(&Local))
+                .unwrap_or_else(Local::now),
+            cpu_percent: self.cpu_percent,
+            memory_mb: self.memory_mb,
+        }
+    }
+}
+
+/// 当前系统中的进程，不含线程（Linux 下 sysinfo 会把线程也列为进程）
+pub fn snapshot(system: &System) -> Vec<ProcessInfo> {
+    system
+        .processes()
+        .values()
+        .filter(|p| p.thread_kind().is_none())
+        .map(ProcessInfo::from_process)
+        .collect()
+}
+
+pub struct ProcessTree {
+    processes: HashMap<u32, ProcessInfo>,
+    children: HashMap<u32, Vec<u32>>,
+}
+
+impl ProcessTree {
+    pub fn new(processes: Vec<ProcessInfo>) -> Self {
+        let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+        for process in &processes {
+            if let Some(parent) = process.parent {
+                children.entry(parent).or_default().push(process.pid);
+            }
+        }
+        for pids in children.values_mut() {
+            pids.sort_unstable();
+        }
+        Self {
+            processes: processes.into_iter().map(|p| (p.pid, p)).collect(),
+            children,
+        }
+    }
+
+    pub fn get(&self, pid: u32) -> Option<&ProcessInfo> {
+        self.processes.get(&pid)
+    }
+
+    pub fn processes(&self) -> impl Iterator<Item = &ProcessInfo> {
+        self.processes.values()
+    }
+
+    /// 进程是否仍是同一个（pid 存在且启动时间一致）
+    pub fn is_alive(&self, pid: u32, start_time: u64) -> bool {
+        self.get(pid).is_some_and(|p| p.start_time == start_time)
+    }
+
+    /// 从父进程一直到根的 pid
+    pub fn ancestors(&self, pid: u32) -> Vec<u32> {
+        let mut result = Vec::new();
+        let mut seen = HashSet::from([pid]);
+        let mut current = self.get(pid).and_then(|p| p.parent);
+        while let Some(parent) = current {
+            // pid 复用可能让父子关系成环
+            if !seen.insert(parent) {
+                break;
+            }
+            result.push(parent);
+            current = self.get(parent).and_then(|p| p.parent);
+        }
+        result
+    }
+
+    /// 全部子孙进程，按层级广度优先
+    pub fn descendants(&self, pid: u32) -> Vec<&ProcessInfo> {
+        let mut result = Vec::new();
+        let mut seen = HashSet::from([pid]);
+        let mut queue = std::collections::VecDeque::from([pid]);
+        while let Some(current) = queue.pop_front() {
+            for child in self.children.get(&current).into_iter().flatten() {
+                if !seen.insert(*child) {
+                    continue;
+                }
+                if let Some(process) = self.get(*child) {
+                    result.push(process);
+                    queue.push_back(*child);
+                }
+            }
+        }
+        result
+    }
+}
@@ -0,0 +1,81 @@
+//! 重放 ccm-hook 在 monitor 未运行时写入的 spool，写入端见 `ccm_hook::spool`
+
+use crate::database::Database;
+use crate::ingest;
+use crate::models::HookEvent;
+use ccm_hook::monitor_dir;
+use ccm_hook::spool::SPOOL_FILE;
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+const DRAINING_EXT: &str = "draining";
+
+#[derive(Debug, Default)]
+pub struct ReplayStats {
+    pub replayed: usize,
+    pub duplicates: usize,
+    pub invalid: usize,
+}
+
+/// 把当前 spool 改名后独占处理，新事件会写入新的 spool 文件
+pub fn take() -> std::io::Result<Vec<PathBuf>> {
+    let Some(dir) = monitor_dir() else {
+        return Ok(Vec::new());
+    };
+    let spool = dir.join(SPOOL_FILE);
+    if spool.exists() {
+        let draining = dir.join(format!(
+            "spool-{}.{}",
+            chrono::Local::now().format("%Y%m%d%H%M%S%3f"),
+            DRAINING_EXT
+        ));
+        std::fs::rename(&spool, draining)?;
+    }
+
+    // 包括上次中途失败遗留的文件
+    let mut files: Vec<PathBuf> = std::fs::read_dir(&dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == DRAINING_EXT))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// 重放 spool 中的事件；事件 id 在文件内和数据库中都会去重
+pub fn replay(db: &Database, files: &[PathBuf]) -> Result<ReplayStats, String> {
+    let mut stats = ReplayStats::default();
+    let mut seen = HashSet::new();
+
+    for file in files {
+        let content = std::fs::read_to_string(file).map_err(|e| e.to_string())?;
+
+        for line in content.lines().filter(|l| !l.trim().is_empty()) {
+            let event: HookEvent = match serde_json::from_str(line) {
+                Ok(event) => event,
+                Err(_) => {
+                    stats.invalid += 1;
+                    continue;
+                }
+            };
+            if event.validate().is_err() {
+                stats.invalid += 1;
+                continue;
+            }
+
+            let key = event.event_id.clone().unwrap_or_else(|| line.to_string());
+            if !seen.insert(key) {
+                stats.duplicates += 1;
+                continue;
+            }
+
+            match ingest::ingest_hook_event(db, &event).map_err(|e| e.to_string())? {
+                Some(_) => stats.replayed += 1,
+                None => stats.duplicates += 1,
+            }
+        }
+
+        std::fs::remove_file(file).map_err(|e| e.to_string())?;
+    }
+
+    Ok(stats)
+}
@@ -0,0 +1,202 @@
+//! 优雅终止实例：依次发送 SIGINT、SIGTERM、SIGKILL，覆盖实例所在的进程组和整棵进程树。
+//! 每一步通过事件总线报告进度，并记入实例的生命周期记录。
+
+use crate::database::Database;
+use crate::events::{EventBus, MonitorEvent};
+use crate::models::{TerminationProgress, TerminationStatus};
+use crate::monitor::ProcessMonitor;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessStatus, Signal, System};
+use tokio::sync::Mutex;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+/// SIGKILL 后等待内核回收进程的时间
+const KILL_WAIT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerminationConfig {
+    /// 发送 SIGINT 后等待的秒数，给 Claude 保存会话记录的时间
+    #[serde(default = "default_interrupt_grace_secs")]
+    pub interrupt_grace_secs: u64,
+    /// 发送 SIGTERM 后等待的秒数，超时后发送 SIGKILL
+    #[serde(default = "default_term_grace_secs")]
+    pub term_grace_secs: u64,
+}
+
+impl Default for TerminationConfig {
+    fn default() -> Self {
+        Self {
+            interrupt_grace_secs: default_interrupt_grace_secs(),
+            term_grace_secs: default_term_grace_secs(),
+        }
+    }
+}
+
+fn default_interrupt_grace_secs() -> u64 {
+    5
+}
+
+fn default_term_grace_secs() -> u64 {
+    5
+}
+
+/// 要终止的进程，带上启动时间，避免误杀复用了 pid 的新进程
+#[derive(Debug, Clone, Copy)]
+pub struct Target {
+    pub pid: u32,
+    pub start_time: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct TerminationPlan {
+    pub instance_id: String,
+    pub pid: u32,
+    /// 实例及其全部子孙进程
+    pub targets: Vec<Target>,
+    /// 实例或其包装进程担任组长的进程组，可整组发送信号
+    pub process_group: Option<u32>,
+}
+
+pub async fn terminate(
+    plan: TerminationPlan,
+    config: TerminationConfig,
+    db: Arc<Mutex<Database>>,
+    bus: Arc<EventBus>,
+    monitor: Arc<Mutex<ProcessMonitor>>,
+) {
+    let started = Instant::now();
+    record(&db, &plan, "termination_requested", format!("{} processes", plan.targets.len())).await;
+
+    let stages = [
+        (Signal::Interrupt, "SIGINT", Duration::from_secs(config.interrupt_grace_secs)),
+        (Signal::Term, "SIGTERM", Duration::from_secs(config.term_grace_secs)),
+        (Signal::Kill, "SIGKILL", KILL_WAIT),
+    ];
+    let mut last_signal = "none";
+    for (signal, name, grace) in stages {
+        let survivors = alive(&plan.targets);
+        if survivors.is_empty() {
+            break;
+        }
+        if !send_signal(plan.process_group, &survivors, signal) {
+            // 平台不支持该信号，如 Windows 只能强制结束
+            continue;
+        }
+        last_signal = name;
+        let message = format!("Sent {} to {} processes", name, survivors.len());
+        report(&bus, &plan, name, TerminationStatus::SignalSent, message);
+        record(&db, &plan, "signal_sent", name.to_string()).await;
+
+        let deadline = Instant::now() + grace;
+        while Instant::now() < deadline && !alive(&plan.targets).is_empty() {
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    let survivors = alive(&plan.targets);
+    if survivors.is_empty() {
+        let message = format!("Exited after {} in {:.1}s", last_signal, started.elapsed().as_secs_f64());
+        report(&bus, &plan, last_signal, TerminationStatus::Exited, message.clone());
+        record(&db, &plan, "terminated", message).await;
+    } else {
+        // 实例仍在运行时不应把之后的退出算作被监控终止
+        monitor.lock().await.cancel_termination(plan.pid);
+        let pids: Vec<String> = survivors.iter().map(|t| t.pid.to_string()).collect();
+        let message = format!("Still running after {}: {}", last_signal, pids.join(", "));
+        report(&bus, &plan, last_signal, TerminationStatus::Failed, message.clone());
+        record(&db, &plan, "termination_failed", message).await;
+    }
+}
+
+/// 仍在运行的目标进程；僵尸进程已经退出，只是还没被父进程回收
+fn alive(targets: &[Target]) -> Vec<Target> {
+    let mut system = System::new();
+    targets
+        .iter()
+        .copied()
+        .filter(|target| {
+            let pid = Pid::from_u32(target.pid);
+            system.refresh_process_specifics(pid, ProcessRefreshKind::new())
+                && system
+                    .process(pid)
+                    .is_some_and(|p| p.start_time() == target.start_time && p.status() != ProcessStatus::Zombie)
+        })
+        .collect()
+}
+
+/// 先对进程组发送，再逐个发给不在该组里的目标进程（覆盖 setsid 启动的 dev server 等组外进程）；
+/// 组内进程只收到一次信号，重复的 SIGINT 会让 Claude 跳过保存直接退出。返回是否有进程收到信号
+pub fn send_signal(process_group: Option<u32>, targets: &[Target], signal: Signal) -> bool {
+    let mut sent = false;
+
+    #[cfg(unix)]
+    let individual = match process_group {
+        Some(group) => {
+            let signum = match signal {
+                Signal::Interrupt => libc::SIGINT,
+                Signal::Term => libc::SIGTERM,
+                Signal::Stop => libc::SIGSTOP,
+                Signal::Continue => libc::SIGCONT,
+                _ => libc::SIGKILL,
+            };
+            // 发送前确定组成员，收到 SIGKILL 的进程退出后就查不到所属进程组了
+            let outside: Vec<Target> = targets
+                .iter()
+                .copied()
+                .filter(|target| self::process_group(target.pid) != Some(group))
+                .collect();
+            // SAFETY: killpg 只读取参数，组长已确认是实例或其包装进程
+            sent = unsafe { libc::killpg(group as libc::pid_t, signum) } == 0;
+            if sent {
+                outside
+            } else {
+                targets.to_vec()
+            }
+        }
+        None => targets.to_vec(),
+    };
+    #[cfg(not(unix))]
+    let individual = targets.to_vec();
+
+    let mut system = System::new();
+    for target in individual {
+        let pid = Pid::from_u32(target.pid);
+        system.refresh_process_specifics(pid, ProcessRefreshKind::new());
+        let Some(process) = system.process(pid).filter(|p| p.start_time() == target.start_time) else {
+            continue;
+        };
+        sent |= process.kill_with(signal) == Some(true);
+    }
+    sent
+}
+
+fn report(bus: &EventBus, plan: &TerminationPlan, signal: &str, status: TerminationStatus, message: String) {
+    bus.publish(MonitorEvent::TerminationProgress(TerminationProgress {
+        instance_id: plan.instance_id.clone(),
+        pid: plan.pid,
+        signal: signal.to_string(),
+        status,
+        message,
+    }));
+}
+
+async fn record(db: &Mutex<Database>, plan: &TerminationPlan, action: &str, detail: String) {
+    if let Err(e) = db.lock().await.insert_lifecycle_event(&plan.instance_id, action, Some(&detail)) {
+        eprintln!("Failed to record lifecycle event: {}", e);
+    }
+}
+
+/// 进程所在的进程组
+#[cfg(unix)]
+pub fn process_group(pid: u32) -> Option<u32> {
+    // SAFETY: getpgid 只读取参数
+    let group = unsafe { libc::getpgid(pid as libc::pid_t) };
+    (group > 0).then_some(group as u32)
+}
+
+#[cfg(not(unix))]
+pub fn process_group(_pid: u32) -> Option<u32> {
+    None
+}
@@ -0,0 +1,68 @@
+import { useState } from 'react'
+import { Alert } from '../types'
+
+interface AlertsProps {
+  firing: Alert[]
+  resolved: Alert[]
+}
+
+const ACTION_LABELS: Record<Alert['action'], string> = {
+  none: '',
+  suspend: '已挂起',
+  kill: '已终止'
+}
+
+function formatTime(isoString: string): string {
+  return new Date(isoString).toLocaleTimeString('zh-CN')
+}
+
+export function Alerts({ firing, resolved }: AlertsProps) {
+  const [showResolved, setShowResolved] = useState(false)
+  if (firing.length === 0 && resolved.length === 0) return null
+
+  const visible = showResolved ? [...firing, ...resolved] : firing
+
+  return (
+    <div className="card">
+      <div className="card-header">
+        <span className="card-title">告警 ({firing.length})</span>
+        {resolved.length > 0 && (
+          <button className="btn btn-sm" onClick={() => setShowResolved(!showResolved)}>
+            {showResolved ? '隐藏已解除' : `显示已解除 (${resolved.length})`}
+          </button>
+        )}
+      </div>
+      {visible.map((alert) => (
+        <div key={alert.id} className="instance-item">
+          <div className="instance-info">
+            <div className="instance-pid">
+              PID: {alert.pid} · {alert.rule_id}
+              {ACTION_LABELS[alert.action] && (
+                <span className="instance-tag instance-tag-danger">{ACTION_LABELS[alert.action]}</span>
+              )}
+            </div>
+            <div className="instance-cmdline" title={alert.message}>
+              {alert.message}
+            </div>
+          </div>
+          <div className="instance-meta">
+            <span title={alert.cwd}>{alert.cwd}</span>
+            <span>触发: {formatTime(alert.fired_at)}</span>
+            {alert.resolved_at && <span>解除: {formatTime(alert.resolved_at)}</span>}
+          </div>
+          {alert.status === 'firing' ? (
+            <span className="badge badge-alert">
+              <span className="badge-dot"></span>
+              告警中
+            </span>
+          ) : (
+            <span className="badge badge-stopped">
+              <span className="badge-dot"></span>
+              已解除
+            </span>
+          )}
+        </div>
+      ))}
+    </div>
+  )
+}
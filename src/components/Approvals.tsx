@@ -0,0 +1,52 @@
+import { PendingApproval } from '../types'
+
+interface ApprovalsProps {
+  approvals: PendingApproval[]
+  onApprove: (id: string) => void
+  onDeny: (id: string, reason?: string) => void
+}
+
+function describeInput(approval: PendingApproval): string {
+  const input = approval.tool_input as Record<string, unknown> | null
+  if (!input) return ''
+  const value = input.command ?? input.file_path ?? input.path
+  return typeof value === 'string' ? value : JSON.stringify(input)
+}
+
+export function Approvals({ approvals, onApprove, onDeny }: ApprovalsProps) {
+  if (approvals.length === 0) return null
+
+  return (
+    <div className="card">
+      <div className="card-header">
+        <span className="card-title">待审批的工具调用 ({approvals.length})</span>
+      </div>
+      {approvals.map((approval) => (
+        <div key={approval.id} className="instance-item">
+          <div className="instance-info">
+            <div className="instance-pid">{approval.tool_name}</div>
+            <div className="instance-cmdline" title={describeInput(approval)}>
+              {describeInput(approval)}
+            </div>
+          </div>
+          <div className="instance-meta">
+            <span title={approval.cwd}>{approval.cwd}</span>
+            <span>{approval.reason}</span>
+            <span>截止: {new Date(approval.expires_at).toLocaleTimeString('zh-CN')}</span>
+          </div>
+          <div className="instance-actions">
+            <button className="btn btn-sm btn-primary" onClick={() => onApprove(approval.id)}>
+              允许
+            </button>
+            <button
+              className="btn btn-sm btn-danger"
+              onClick={() => onDeny(approval.id, prompt('拒绝原因（可选）') ?? undefined)}
+            >
+              拒绝
+            </button>
+          </div>
+        </div>
+      ))}
+    </div>
+  )
+}
@@ -1,12 +1,21 @@
 import { useState, useMemo } from 'react'
-import { ClaudeInstance, InstallationStatus } from '../types'
+import { invoke } from '@tauri-apps/api/tauri'
+import { ChildProcess, ClaudeInstance, InstallationStatus, OrphanProcess, TerminationProgress } from '../types'
+import { useAlerts, useApprovals } from '../hooks'
+import { Alerts } from './Alerts'
+import { Approvals } from './Approvals'
+import { Orphans } from './Orphans'
 
 interface DashboardProps {
   instances: ClaudeInstance[]
+  orphans: OrphanProcess[]
+  terminations: Record<string, TerminationProgress>
   installStatus: InstallationStatus | null
   loading: boolean
   onRefresh: () => void
-  onKillInstance: (pid: number) => void
+  onKillInstance: (instanceId: string) => void
+  onSuspendInstance: (instanceId: string) => void
+  onResumeInstance: (instanceId: string) => void
 }
 
 interface InstanceGroup {
@@ -21,17 +30,34 @@ function formatTime(isoString: string): string {
   return date.toLocaleTimeString('zh-CN', { hour: '2-digit', minute: '2-digit' })
 }
 
+const MB = 1024 * 1024
+
+/** 用量 / 上限，上限为 null 表示不限 */
+function formatLimit(current: number, max: number | null): string {
+  const value = Number.isInteger(current) ? String(current) : current.toFixed(1)
+  return max === null ? `${value} / 不限` : `${value} / ${max.toFixed(0)}`
+}
+
 export function Dashboard({
   instances,
+  orphans,
+  terminations,
   installStatus,
   loading,
   onRefresh,
-  onKillInstance
+  onKillInstance,
+  onSuspendInstance,
+  onResumeInstance
 }: DashboardProps) {
   const [expandedGroups, setExpandedGroups] = useState<Set<string>>(new Set())
+  const [children, setChildren] = useState<Record<string, ChildProcess[]>>({})
+  const [skippedOnly, setSkippedOnly] = useState(false)
+  const { approvals, approve, deny } = useApprovals()
+  const { firing, resolved } = useAlerts()
 
   const groups = useMemo(() => {
-    const grouped = instances.reduce((acc, inst) => {
+    const visible = skippedOnly ? instances.filter(i => i.skip_permissions) : instances
+    const grouped = visible.reduce((acc, inst) => {
       const key = inst.cwd || '未知路径'
       if (!acc[key]) acc[key] = []
       acc[key].push(inst)
@@ -42,11 +68,11 @@ export function Dashboard({
       .map(([cwd, insts]): InstanceGroup => ({
         cwd,
         instances: insts,
-        totalCpu: insts.reduce((sum, i) => sum + i.cpu_percent, 0),
-        totalMem: insts.reduce((sum, i) => sum + i.memory_mb, 0)
+        totalCpu: insts.reduce((sum, i) => sum + (i.tree_cpu_percent || i.cpu_percent), 0),
+        totalMem: insts.reduce((sum, i) => sum + (i.tree_memory_mb || i.memory_mb), 0)
       }))
       .sort((a, b) => a.cwd.localeCompare(b.cwd))
-  }, [instances])
+  }, [instances, skippedOnly])
 
   const toggleGroup = (cwd: string) => {
     setExpandedGroups(prev => {
@@ -60,6 +86,23 @@ export function Dashboard({
     })
   }
 
+  const toggleChildren = async (instanceId: string) => {
+    if (children[instanceId]) {
+      setChildren(prev => {
+        const next = { ...prev }
+        delete next[instanceId]
+        return next
+      })
+      return
+    }
+    try {
+      const list = await invoke<ChildProcess[]>('get_instance_children', { instanceId })
+      setChildren(prev => ({ ...prev, [instanceId]: list }))
+    } catch (e) {
+      console.error('Failed to load child processes:', e)
+    }
+  }
+
   if (!installStatus?.installed) {
     return (
       <div className="empty-state">
@@ -74,12 +117,25 @@ export function Dashboard({
     <>
       <div className="main-header">
         <h2>运行中的实例 ({instances.length})</h2>
-        <button className="btn btn-primary" onClick={onRefresh} disabled={loading}>
-          {loading ? '刷新中...' : '刷新'}
-        </button>
+        <div className="instance-actions">
+          <label className="instance-filter">
+            <input
+              type="checkbox"
+              checked={skippedOnly}
+              onChange={(e) => setSkippedOnly(e.target.checked)}
+            />
+            仅看跳过权限的实例
+          </label>
+          <button className="btn btn-primary" onClick={onRefresh} disabled={loading}>
+            {loading ? '刷新中...' : '刷新'}
+          </button>
+        </div>
       </div>
 
       <div className="content-scroll">
+      <Approvals approvals={approvals} onApprove={approve} onDeny={deny} />
+      <Alerts firing={firing} resolved={resolved} />
+      <Orphans orphans={orphans} />
       {instances.length === 0 ? (
         <div className="empty-state">
           <div className="empty-state-icon">○</div>
@@ -111,9 +167,21 @@ export function Dashboard({
 
                 <div className={`group-instances ${isExpanded ? 'expanded' : ''}`}>
                   {group.instances.map((inst) => (
-                    <div key={inst.id} className="instance-item">
+                    <div key={inst.id}>
+                    <div className="instance-item">
                       <div className="instance-info">
-                        <div className="instance-pid">PID: {inst.pid}</div>
+                        <div className="instance-pid">
+                          PID: {inst.pid}
+                          {inst.model && <span className="instance-tag">{inst.model}</span>}
+                          {inst.print_mode && <span className="instance-tag">print</span>}
+                          {inst.resumed && (
+                            <span className="instance-tag" title={inst.resume_session_id ?? undefined}>resumed</span>
+                          )}
+                          {inst.permission_mode && <span className="instance-tag">{inst.permission_mode}</span>}
+                          {inst.skip_permissions && (
+                            <span className="instance-tag instance-tag-danger">跳过权限</span>
+                          )}
+                        </div>
                         <div
                           className="instance-cmdline"
                           title={inst.cmdline}
@@ -126,19 +194,60 @@ export function Dashboard({
                       <div className="instance-meta">
                         <span>CPU: {inst.cpu_percent.toFixed(1)}%</span>
                         <span>MEM: {inst.memory_mb.toFixed(1)} MB</span>
+                        <span title="含子进程">进程树: {inst.tree_cpu_percent.toFixed(1)}% / {inst.tree_memory_mb.toFixed(1)} MB</span>
+                        {inst.cgroup && (
+                          <span title={`cgroup ${inst.cgroup.name}，节流 ${(inst.cgroup.throttled_usec / 1e6).toFixed(1)}s，OOM ${inst.cgroup.oom_kills} 次`}>
+                            cgroup: {formatLimit(inst.cgroup.memory_bytes / MB, inst.cgroup.memory_max_bytes === null ? null : inst.cgroup.memory_max_bytes / MB)} MB
+                            {' · '}CPU 上限 {inst.cgroup.cpu_max_percent === null ? '无' : `${inst.cgroup.cpu_max_percent.toFixed(0)}%`}
+                            {' · '}进程 {formatLimit(inst.cgroup.pids, inst.cgroup.pids_max)}
+                            {inst.cgroup.nr_throttled > 0 && ` · 节流 ${inst.cgroup.nr_throttled} 次`}
+                          </span>
+                        )}
                         <span>启动: {formatTime(inst.start_time)}</span>
+                        {terminations[inst.id] && <span>{terminations[inst.id].message}</span>}
                       </div>
-                      <span className="badge badge-running">
-                        <span className="badge-dot"></span>
-                        运行中
-                      </span>
+                      {inst.status === 'suspended' ? (
+                        <span className="badge badge-stopped">
+                          <span className="badge-dot"></span>
+                          已挂起
+                        </span>
+                      ) : (
+                        <span className="badge badge-running">
+                          <span className="badge-dot"></span>
+                          运行中
+                        </span>
+                      )}
                       <div className="instance-actions">
+                        <button
+                          className="btn btn-sm"
+                          disabled={inst.child_count === 0}
+                          onClick={(e) => {
+                            e.stopPropagation()
+                            toggleChildren(inst.id)
+                          }}
+                        >
+                          子进程 ({inst.child_count})
+                        </button>
+                        <button
+                          className="btn btn-sm"
+                          onClick={(e) => {
+                            e.stopPropagation()
+                            if (inst.status === 'suspended') {
+                              onResumeInstance(inst.id)
+                            } else {
+                              onSuspendInstance(inst.id)
+                            }
+                          }}
+                        >
+                          {inst.status === 'suspended' ? '恢复' : '挂起'}
+                        </button>
                         <button
                           className="btn btn-sm btn-danger"
+                          disabled={terminations[inst.id]?.status === 'signal_sent'}
                           onClick={(e) => {
                             e.stopPropagation()
-                            if (confirm(`确定要终止 PID ${inst.pid} 的实例吗？`)) {
-                              onKillInstance(inst.pid)
+                            if (confirm(`确定要终止 PID ${inst.pid} 的实例及其子进程吗？`)) {
+                              onKillInstance(inst.id)
                             }
                           }}
                         >
@@ -146,6 +255,21 @@ export function Dashboard({
                         </button>
                       </div>
                     </div>
+                    {children[inst.id]?.map((child) => (
+                      <div key={child.pid} className="instance-item child-process">
+                        <div className="instance-info">
+                          <div className="instance-pid">PID: {child.pid} · {child.name}</div>
+                          <div className="instance-cmdline" title={child.cmdline}>
+                            {child.cmdline}
+                          </div>
+                        </div>
+                        <div className="instance-meta">
+                          <span>CPU: {child.cpu_percent.toFixed(1)}%</span>
+                          <span>MEM: {child.memory_mb.toFixed(1)} MB</span>
+                        </div>
+                      </div>
+                    ))}
+                    </div>
                   ))}
                 </div>
               </div>
@@ -1,12 +1,20 @@
-import { AppConfig } from '../types'
+import { useState } from 'react'
+import { AppConfig, HookServerStatus } from '../types'
+import { Notifications } from './Notifications'
 
 interface HooksProps {
   config: AppConfig
   installing: boolean
+  serverStatus: HookServerStatus | null
+  onRefreshStatus: () => Promise<void>
   onToggle: () => Promise<void>
+  onInstallProject: (projectDir: string) => Promise<string>
+  onUninstallProject: (projectDir: string) => Promise<string>
 }
 
-export function Hooks({ config, installing, onToggle }: HooksProps) {
+export function Hooks({ config, installing, serverStatus, onRefreshStatus, onToggle, onInstallProject, onUninstallProject }: HooksProps) {
+  const [projectDir, setProjectDir] = useState('')
+
   const handleToggle = async () => {
     try {
       await onToggle()
@@ -15,6 +23,15 @@ export function Hooks({ config, installing, onToggle }: HooksProps) {
     }
   }
 
+  const handleProject = async (action: (dir: string) => Promise<string>) => {
+    if (!projectDir.trim()) return
+    try {
+      alert(await action(projectDir.trim()))
+    } catch (e) {
+      alert('操作失败: ' + e)
+    }
+  }
+
   return (
     <>
       <div className="main-header">
@@ -27,12 +44,12 @@ export function Hooks({ config, installing, onToggle }: HooksProps) {
             <span className="card-title">自动 Hook 注入</span>
           </div>
           <p style={{ color: 'var(--text-secondary)', fontSize: '13px', marginBottom: '16px' }}>
-            启用后，工具会自动生成 Hook 脚本，捕获 Claude Code 的事件和会话内容。
+            启用后，工具会把 Hook 写入 ~/.claude/settings.json，捕获 Claude Code 的事件和会话内容。
           </p>
           <div className="setting-item">
             <div>
               <div className="setting-label">启用 Hook</div>
-              <div className="setting-description">捕获任务开始/结束、提示词、响应等事件</div>
+              <div className="setting-description">捕获会话开始/结束、提示词、工具调用等事件</div>
             </div>
             <button
               className={`toggle ${config.hook_enabled ? 'active' : ''}`}
@@ -50,18 +67,54 @@ export function Hooks({ config, installing, onToggle }: HooksProps) {
 
         <div className="card">
           <div className="card-header">
-            <span className="card-title">手动使用 Hook</span>
+            <span className="card-title">Hook 服务</span>
+            <button className="btn" onClick={onRefreshStatus}>刷新</button>
+          </div>
+          {serverStatus?.running ? (
+            <div className="code-block">
+              {`TCP:    127.0.0.1:${serverStatus.port}` +
+                (serverStatus.socket_path ? `\nSocket: ${serverStatus.socket_path}` : '')}
+            </div>
+          ) : (
+            <p style={{ color: 'var(--error)', fontSize: '13px' }}>
+              服务未启动{serverStatus?.error ? `：${serverStatus.error}` : ''}
+            </p>
+          )}
+        </div>
+
+        <Notifications config={config.notifications} />
+
+        <div className="card">
+          <div className="card-header">
+            <span className="card-title">项目级 Hook</span>
           </div>
           <p style={{ color: 'var(--text-secondary)', fontSize: '13px', marginBottom: '12px' }}>
-            如果不想全局启用，可以手动使用带 Hook 的 wrapper：
+            只对某个项目启用时，写入该项目的 .claude/settings.json：
           </p>
-          <div className="code-block">{`# 使用 wrapper 启动
-cd ~/.claude-monitor
-./claude-with-hook
-
-# 或设置环境变量
-export CLAUDE_CODE_HOOKS=~/.claude-monitor/hook.sh
-claude`}</div>
+          <div style={{ display: 'flex', gap: '8px' }}>
+            <input
+              type="text"
+              placeholder="项目路径"
+              className="btn"
+              style={{ textAlign: 'left', flex: 1, cursor: 'text' }}
+              value={projectDir}
+              onChange={(e) => setProjectDir(e.target.value)}
+            />
+            <button
+              className="btn btn-primary"
+              disabled={installing}
+              onClick={() => handleProject(onInstallProject)}
+            >
+              安装
+            </button>
+            <button
+              className="btn"
+              disabled={installing}
+              onClick={() => handleProject(onUninstallProject)}
+            >
+              卸载
+            </button>
+          </div>
         </div>
 
         <div className="card">
@@ -70,8 +123,11 @@ claude`}</div>
           </div>
           <div className="code-block" style={{ marginBottom: '12px' }}>
             {`~/.claude-monitor/
-  ├── hook.sh          # Hook 脚本
-  └── claude-with-hook # Wrapper 脚本`}
+  ├── bin/ccm-hook      # Hook 客户端
+  └── server.json       # 服务实际监听的端口
+~/.claude/
+  ├── settings.json    # 合并写入的 hooks 配置
+  └── settings.json.bak-*  # 每次写入前的备份`}
           </div>
         </div>
       </div>
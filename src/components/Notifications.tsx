@@ -0,0 +1,68 @@
+import { useState } from 'react'
+import { invoke } from '@tauri-apps/api/tauri'
+import { NotificationChannel, NotificationConfig } from '../types'
+
+interface NotificationsProps {
+  config: NotificationConfig
+}
+
+function describeChannel(channel: NotificationChannel): string {
+  switch (channel.type) {
+    case 'desktop':
+      return '桌面通知'
+    case 'webhook':
+      return `Webhook · ${channel.url}`
+    case 'smtp':
+      return `邮件 · ${channel.to.join(', ')}`
+    case 'command':
+      return `命令 · ${channel.program}`
+  }
+}
+
+export function Notifications({ config }: NotificationsProps) {
+  const [results, setResults] = useState<Record<string, string>>({})
+
+  const sendTest = async (channelId: string) => {
+    setResults((prev) => ({ ...prev, [channelId]: '发送中...' }))
+    try {
+      await invoke('send_test_notification', { channelId })
+      setResults((prev) => ({ ...prev, [channelId]: '已发送' }))
+    } catch (e) {
+      setResults((prev) => ({ ...prev, [channelId]: '失败: ' + e }))
+    }
+  }
+
+  return (
+    <div className="card">
+      <div className="card-header">
+        <span className="card-title">通知</span>
+        <span className={`badge ${config.enabled ? 'badge-running' : 'badge-stopped'}`}>
+          <span className="badge-dot"></span>
+          {config.enabled ? '已启用' : '未启用'}
+        </span>
+      </div>
+      <p style={{ color: 'var(--text-secondary)', fontSize: '13px', marginBottom: '12px' }}>
+        任务完成、等待输入和告警按 config.json 中 notifications 的路由规则发送
+        {config.quiet_hours && `，${config.quiet_hours.start}-${config.quiet_hours.end} 为静默时段`}。
+      </p>
+      {config.channels.length === 0 ? (
+        <p style={{ color: 'var(--text-muted)', fontSize: '13px' }}>尚未配置通知渠道</p>
+      ) : (
+        config.channels.map((channel) => (
+          <div key={channel.id} className="setting-item">
+            <div>
+              <div className="setting-label">{channel.id}</div>
+              <div className="setting-description">
+                {describeChannel(channel)}
+                {results[channel.id] && ` · ${results[channel.id]}`}
+              </div>
+            </div>
+            <button className="btn btn-sm" onClick={() => sendTest(channel.id)}>
+              测试
+            </button>
+          </div>
+        ))
+      )}
+    </div>
+  )
+}
@@ -0,0 +1,33 @@
+import { OrphanProcess } from '../types'
+
+interface OrphansProps {
+  orphans: OrphanProcess[]
+}
+
+export function Orphans({ orphans }: OrphansProps) {
+  if (orphans.length === 0) return null
+
+  return (
+    <div className="card">
+      <div className="card-header">
+        <span className="card-title">孤儿进程 ({orphans.length})</span>
+      </div>
+      {orphans.map((orphan) => (
+        <div key={orphan.pid} className="instance-item">
+          <div className="instance-info">
+            <div className="instance-pid">PID: {orphan.pid} · {orphan.name}</div>
+            <div className="instance-cmdline" title={orphan.cmdline}>
+              {orphan.cmdline}
+            </div>
+          </div>
+          <div className="instance-meta">
+            <span title={orphan.cwd}>{orphan.cwd}</span>
+            <span>CPU: {orphan.cpu_percent.toFixed(1)}%</span>
+            <span>MEM: {orphan.memory_mb.toFixed(1)} MB</span>
+            <span>发现: {new Date(orphan.detected_at).toLocaleTimeString('zh-CN')}</span>
+          </div>
+        </div>
+      ))}
+    </div>
+  )
+}
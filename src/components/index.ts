@@ -3,3 +3,7 @@ export { Dashboard } from './Dashboard'
 export { Install } from './Install'
 export { Hooks } from './Hooks'
 export { History } from './History'
+export { Approvals } from './Approvals'
+export { Orphans } from './Orphans'
+export { Alerts } from './Alerts'
+export { Notifications } from './Notifications'
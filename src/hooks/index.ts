@@ -2,3 +2,6 @@ export { useConfig } from './useConfig'
 export { useInstances } from './useInstances'
 export { useHook } from './useHook'
 export { useInstaller } from './useInstaller'
+export { useApprovals } from './useApprovals'
+export { useMonitorEvents } from './useMonitorEvents'
+export { useAlerts } from './useAlerts'
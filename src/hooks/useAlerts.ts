@@ -0,0 +1,34 @@
+import { useState, useEffect, useCallback } from 'react'
+import { invoke } from '@tauri-apps/api/tauri'
+import { Alert, MonitorEventEnvelope } from '../types'
+import { useMonitorEvents } from './useMonitorEvents'
+
+/** 正在触发的告警和最近解除的告警，随 alert_fired / alert_resolved 事件更新 */
+export function useAlerts(limit = 20) {
+  const [alerts, setAlerts] = useState<Alert[]>([])
+
+  const loadAlerts = useCallback(async () => {
+    try {
+      setAlerts(await invoke<Alert[]>('get_alerts', { status: null, limit }))
+    } catch (e) {
+      console.error('Failed to load alerts:', e)
+    }
+  }, [limit])
+
+  useMonitorEvents(useCallback((event: MonitorEventEnvelope) => {
+    if (event.kind === 'alert_fired' || event.kind === 'alert_resolved') {
+      const alert = event.payload
+      setAlerts((prev) => [alert, ...prev.filter((a) => a.id !== alert.id)].slice(0, limit))
+    }
+  }, [limit]))
+
+  useEffect(() => {
+    loadAlerts()
+  }, [loadAlerts])
+
+  return {
+    firing: alerts.filter((a) => a.status === 'firing'),
+    resolved: alerts.filter((a) => a.status === 'resolved'),
+    reload: loadAlerts
+  }
+}
@@ -0,0 +1,42 @@
+import { useState, useEffect, useCallback } from 'react'
+import { invoke } from '@tauri-apps/api/tauri'
+import { PendingApproval } from '../types'
+
+export function useApprovals(intervalMs = 2000) {
+  const [approvals, setApprovals] = useState<PendingApproval[]>([])
+
+  const loadApprovals = useCallback(async () => {
+    try {
+      const data = await invoke<PendingApproval[]>('list_pending_approvals')
+      setApprovals(data)
+    } catch (e) {
+      console.error('Failed to load approvals:', e)
+    }
+  }, [])
+
+  const approve = useCallback(async (id: string) => {
+    try {
+      await invoke('approve_tool_call', { id })
+    } catch (e) {
+      alert('操作失败: ' + e)
+    }
+    await loadApprovals()
+  }, [loadApprovals])
+
+  const deny = useCallback(async (id: string, reason?: string) => {
+    try {
+      await invoke('deny_tool_call', { id, reason: reason || null })
+    } catch (e) {
+      alert('操作失败: ' + e)
+    }
+    await loadApprovals()
+  }, [loadApprovals])
+
+  useEffect(() => {
+    loadApprovals()
+    const timer = setInterval(loadApprovals, intervalMs)
+    return () => clearInterval(timer)
+  }, [loadApprovals, intervalMs])
+
+  return { approvals, approve, deny, reload: loadApprovals }
+}
@@ -5,7 +5,20 @@ import { AppConfig } from '../types'
 const defaultConfig: AppConfig = {
   hook_enabled: false,
   auto_start_monitor: true,
-  polling_interval_secs: 5
+  polling_interval_secs: 5,
+  hook_port: 9876,
+  hook_port_fallbacks: 10,
+  policy_rules: [],
+  approval: { timeout_secs: 120, default_decision: 'ask' },
+  alert_rules: [],
+  termination: { interrupt_grace_secs: 5, term_grace_secs: 5 },
+  cgroup: {
+    enabled: false,
+    scope: 'instance',
+    limits: { cpu_percent: null, memory_max_mb: null, pids_max: null },
+    workspaces: []
+  },
+  notifications: { enabled: false, channels: [], routes: [], quiet_hours: null }
 }
 
 export function useConfig() {
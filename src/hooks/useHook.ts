@@ -1,9 +1,30 @@
-import { useState, useCallback } from 'react'
+import { useState, useCallback, useEffect } from 'react'
 import { invoke } from '@tauri-apps/api/tauri'
-import { AppConfig } from '../types'
+import { AppConfig, HookServerStatus, MonitorEventEnvelope } from '../types'
+import { useMonitorEvents } from './useMonitorEvents'
 
 export function useHook(config: AppConfig, saveConfig: (c: AppConfig) => Promise<void>) {
   const [installing, setInstalling] = useState(false)
+  const [serverStatus, setServerStatus] = useState<HookServerStatus | null>(null)
+
+  const refreshServerStatus = useCallback(async () => {
+    try {
+      setServerStatus(await invoke<HookServerStatus>('get_hook_server_status'))
+    } catch (e) {
+      console.error('Failed to get hook server status:', e)
+    }
+  }, [])
+
+  useEffect(() => {
+    refreshServerStatus()
+  }, [refreshServerStatus])
+
+  // 服务可能在页面加载后才绑定完成
+  useMonitorEvents(useCallback((event: MonitorEventEnvelope) => {
+    if (event.kind === 'hook_server_started') {
+      setServerStatus(event.payload)
+    }
+  }, []))
 
   const installHook = useCallback(async () => {
     setInstalling(true)
@@ -33,6 +54,24 @@ export function useHook(config: AppConfig, saveConfig: (c: AppConfig) => Promise
     }
   }, [config, saveConfig])
 
+  const installProjectHook = useCallback(async (projectDir: string) => {
+    setInstalling(true)
+    try {
+      return await invoke<string>('install_hook', { projectDir })
+    } finally {
+      setInstalling(false)
+    }
+  }, [])
+
+  const uninstallProjectHook = useCallback(async (projectDir: string) => {
+    setInstalling(true)
+    try {
+      return await invoke<string>('uninstall_hook', { projectDir })
+    } finally {
+      setInstalling(false)
+    }
+  }, [])
+
   const toggleHook = useCallback(async () => {
     if (config.hook_enabled) {
       await uninstallHook()
@@ -43,8 +82,12 @@ export function useHook(config: AppConfig, saveConfig: (c: AppConfig) => Promise
 
   return {
     installing,
+    serverStatus,
+    refreshServerStatus,
     installHook,
     uninstallHook,
+    installProjectHook,
+    uninstallProjectHook,
     toggleHook
   }
 }
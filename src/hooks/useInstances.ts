@@ -1,12 +1,14 @@
-import { useState, useEffect, useCallback, useRef } from 'react'
+import { useState, useEffect, useCallback } from 'react'
 import { invoke } from '@tauri-apps/api/tauri'
-import { ClaudeInstance, InstallationStatus } from '../types'
+import { ClaudeInstance, InstallationStatus, MonitorEventEnvelope, OrphanProcess, TerminationProgress } from '../types'
+import { useMonitorEvents } from './useMonitorEvents'
 
 export function useInstances() {
   const [instances, setInstances] = useState<ClaudeInstance[]>([])
+  const [orphans, setOrphans] = useState<OrphanProcess[]>([])
+  const [terminations, setTerminations] = useState<Record<string, TerminationProgress>>({})
   const [installStatus, setInstallStatus] = useState<InstallationStatus | null>(null)
   const [loading, setLoading] = useState(true)
-  const intervalRef = useRef<NodeJS.Timeout | null>(null)
 
   const checkInstallation = useCallback(async () => {
     try {
@@ -28,54 +30,105 @@ export function useInstances() {
     }
   }, [])
 
-  const killInstance = useCallback(async (pid: number) => {
+  const loadOrphans = useCallback(async () => {
     try {
-      await invoke('kill_instance', { pid })
-      await loadInstances()
+      setOrphans(await invoke<OrphanProcess[]>('get_orphan_processes'))
+    } catch (e) {
+      console.error('Failed to load orphan processes:', e)
+    }
+  }, [])
+
+  // 后端逐级发送信号，进度通过 termination_progress 事件返回
+  const killInstance = useCallback(async (instanceId: string) => {
+    try {
+      await invoke('kill_instance', { instanceId })
     } catch (e) {
       alert('终止失败: ' + e)
     }
-  }, [loadInstances])
+  }, [])
+
+  const suspendInstance = useCallback(async (instanceId: string) => {
+    try {
+      await invoke('suspend_instance', { instanceId })
+    } catch (e) {
+      alert('挂起失败: ' + e)
+    }
+  }, [])
+
+  const resumeInstance = useCallback(async (instanceId: string) => {
+    try {
+      await invoke('resume_instance', { instanceId })
+    } catch (e) {
+      alert('恢复失败: ' + e)
+    }
+  }, [])
 
   const refresh = useCallback(async () => {
     setLoading(true)
-    await Promise.all([checkInstallation(), loadInstances()])
+    await Promise.all([checkInstallation(), loadInstances(), loadOrphans()])
     setLoading(false)
-  }, [checkInstallation, loadInstances])
+  }, [checkInstallation, loadInstances, loadOrphans])
 
-  const startPolling = useCallback((intervalMs = 5000) => {
-    if (intervalRef.current) {
-      clearInterval(intervalRef.current)
-    }
-    intervalRef.current = setInterval(() => {
-      loadInstances()
-    }, intervalMs)
-    return () => {
-      if (intervalRef.current) {
-        clearInterval(intervalRef.current)
+  // 后端推送实例变化，不再定时查询数据库
+  useMonitorEvents(useCallback((event: MonitorEventEnvelope) => {
+    switch (event.kind) {
+      case 'instance_appeared':
+        setInstances((prev) => [event.payload, ...prev.filter((i) => i.id !== event.payload.id)])
+        break
+      case 'instance_exited':
+        setInstances((prev) => prev.filter((i) => i.id !== event.payload.instance_id))
+        loadOrphans()
+        break
+      case 'instance_status_changed': {
+        const { instance_id, status } = event.payload
+        setInstances((prev) => prev.map((i) => (i.id === instance_id ? { ...i, status } : i)))
+        break
+      }
+      case 'termination_progress':
+        setTerminations((prev) => ({ ...prev, [event.payload.instance_id]: event.payload }))
+        if (event.payload.status === 'failed') {
+          alert('终止失败: ' + event.payload.message)
+        }
+        break
+      case 'orphan_detected':
+        setOrphans((prev) => [...prev.filter((o) => o.pid !== event.payload.pid), event.payload])
+        break
+      case 'resource_sample': {
+        const sample = event.payload
+        setInstances((prev) =>
+          prev.map((i) =>
+            i.id === sample.instance_id
+              ? {
+                  ...i,
+                  cpu_percent: sample.cpu_percent,
+                  memory_mb: sample.memory_mb,
+                  tree_cpu_percent: sample.tree_cpu_percent,
+                  tree_memory_mb: sample.tree_memory_mb,
+                  child_count: sample.child_count,
+                  cgroup: sample.cgroup,
+                  last_seen: sample.timestamp
+                }
+              : i
+          )
+        )
+        break
       }
     }
-  }, [loadInstances])
+  }, [loadOrphans]))
 
   useEffect(() => {
     refresh()
   }, [refresh])
 
-  useEffect(() => {
-    return () => {
-      if (intervalRef.current) {
-        clearInterval(intervalRef.current)
-      }
-    }
-  }, [])
-
   return {
     instances,
+    orphans,
+    terminations,
     installStatus,
     loading,
     refresh,
     killInstance,
-    startPolling,
-    stopPolling: () => intervalRef.current && clearInterval(intervalRef.current)
+    suspendInstance,
+    resumeInstance
   }
 }
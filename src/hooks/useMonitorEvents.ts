@@ -0,0 +1,56 @@
+import { useEffect, useRef } from 'react'
+import { invoke } from '@tauri-apps/api/tauri'
+import { listen, UnlistenFn } from '@tauri-apps/api/event'
+import { MonitorEventEnvelope, MonitorEventKind } from '../types'
+
+// 与后端 events::EVENT_SCHEMA_VERSION 保持一致
+const EVENT_SCHEMA_VERSION = 1
+
+const EVENT_KINDS: MonitorEventKind[] = [
+  'instance_appeared',
+  'instance_exited',
+  'resource_sample',
+  'hook_event_received',
+  'orphan_detected',
+  'termination_progress',
+  'instance_status_changed',
+  'alert_fired',
+  'alert_resolved',
+  'hook_server_started'
+]
+
+export function useMonitorEvents(onEvent: (event: MonitorEventEnvelope) => void) {
+  const handlerRef = useRef(onEvent)
+  handlerRef.current = onEvent
+
+  useEffect(() => {
+    let unlisteners: UnlistenFn[] = []
+    let disposed = false
+
+    const setup = async () => {
+      const fns = await Promise.all(
+        EVENT_KINDS.map((kind) =>
+          listen<MonitorEventEnvelope>(`monitor:${kind}`, ({ payload }) => {
+            if (payload.version === EVENT_SCHEMA_VERSION) {
+              handlerRef.current(payload)
+            }
+          })
+        )
+      )
+      if (disposed) {
+        fns.forEach((fn) => fn())
+        return
+      }
+      unlisteners = fns
+      await invoke('subscribe_events', { kinds: null })
+    }
+
+    setup().catch((e) => console.error('Failed to subscribe monitor events:', e))
+
+    return () => {
+      disposed = true
+      unlisteners.forEach((fn) => fn())
+      invoke('unsubscribe_events').catch(() => {})
+    }
+  }, [])
+}
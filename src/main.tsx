@@ -1,4 +1,4 @@
-import React, { useState, useEffect, useCallback } from 'react'
+import React, { useState, useCallback } from 'react'
 import ReactDOM from 'react-dom/client'
 import { invoke } from '@tauri-apps/api/tauri'
 import './style.css'
@@ -10,18 +10,10 @@ import { ViewType } from './types'
 function App() {
   const [currentView, setCurrentView] = useState<ViewType>('dashboard')
   const { config, loading: configLoading, saveConfig } = useConfig()
-  const { instances, installStatus, loading, refresh, killInstance, startPolling } = useInstances()
-  const { installing: hookInstalling, toggleHook } = useHook(config, saveConfig)
+  const { instances, orphans, terminations, installStatus, loading, refresh, killInstance, suspendInstance, resumeInstance } = useInstances()
+  const { installing: hookInstalling, serverStatus, refreshServerStatus, toggleHook, installProjectHook, uninstallProjectHook } = useHook(config, saveConfig)
   const { installingNode, installingClaude, installNode, installClaude } = useInstaller()
 
-  // 启动轮询
-  useEffect(() => {
-    if (currentView === 'dashboard') {
-      const stop = startPolling(5000)
-      return stop
-    }
-  }, [currentView, startPolling])
-
   const handleToggleHook = useCallback(async () => {
     await toggleHook()
   }, [toggleHook])
@@ -32,10 +24,14 @@ function App() {
         return (
           <Dashboard
             instances={instances}
+            orphans={orphans}
+            terminations={terminations}
             installStatus={installStatus}
             loading={loading}
             onRefresh={refresh}
             onKillInstance={killInstance}
+            onSuspendInstance={suspendInstance}
+            onResumeInstance={resumeInstance}
           />
         )
       case 'install':
@@ -54,7 +50,11 @@ function App() {
           <Hooks
             config={config}
             installing={hookInstalling}
+            serverStatus={serverStatus}
+            onRefreshStatus={refreshServerStatus}
             onToggle={handleToggleHook}
+            onInstallProject={installProjectHook}
+            onUninstallProject={uninstallProjectHook}
           />
         )
       case 'history':
@@ -14,6 +14,89 @@ export interface ClaudeInstance {
   last_seen: string
   cpu_percent: number
   memory_mb: number
+  end_time: string | null
+  exit_reason: string | null
+  runtime_secs: number | null
+  session_id: string | null
+  tree_cpu_percent: number
+  tree_memory_mb: number
+  child_count: number
+  children: ChildProcess[]
+  model: string | null
+  permission_mode: string | null
+  skip_permissions: boolean
+  print_mode: boolean
+  resumed: boolean
+  resume_session_id: string | null
+  output_format: string | null
+  add_dirs: string[]
+  mcp_config: string | null
+  /** 最近一次资源采样中的 cgroup 统计，仅前端维护 */
+  cgroup?: CgroupStats | null
+}
+
+export interface ChildProcess {
+  pid: number
+  parent_pid: number | null
+  name: string
+  cmdline: string
+  start_time: string
+  cpu_percent: number
+  memory_mb: number
+}
+
+export interface OrphanProcess extends ChildProcess {
+  instance_id: string
+  cwd: string
+  detected_at: string
+}
+
+export interface TerminationProgress {
+  instance_id: string
+  pid: number
+  signal: string
+  status: 'signal_sent' | 'exited' | 'failed'
+  message: string
+}
+
+export interface LifecycleEvent {
+  id: number
+  instance_id: string
+  timestamp: string
+  action: string
+  detail: string | null
+}
+
+export interface Alert {
+  id: string
+  rule_id: string
+  instance_id: string
+  pid: number
+  cwd: string
+  metric: AlertMetric
+  value: number
+  threshold: number
+  action: AlertAction
+  status: 'firing' | 'resolved'
+  message: string
+  fired_at: string
+  resolved_at: string | null
+}
+
+export type AlertMetric = 'cpu' | 'memory' | 'runtime' | 'hook_idle'
+
+export type AlertAction = 'none' | 'suspend' | 'kill'
+
+export interface AlertRule {
+  id: string
+  enabled: boolean
+  workspace: string | null
+  metric: AlertMetric
+  threshold: number
+  for_secs: number
+  clear_after_secs: number
+  action: AlertAction
+  message: string | null
 }
 
 export interface InstanceResource {
@@ -21,6 +104,27 @@ export interface InstanceResource {
   timestamp: string
   cpu_percent: number
   memory_mb: number
+  disk_read_bytes: number
+  disk_write_bytes: number
+  thread_count: number | null
+  fd_count: number | null
+  tree_cpu_percent: number
+  tree_memory_mb: number
+  child_count: number
+  cgroup: CgroupStats | null
+}
+
+export interface CgroupStats {
+  name: string
+  cpu_usage_usec: number
+  nr_throttled: number
+  throttled_usec: number
+  cpu_max_percent: number | null
+  memory_bytes: number
+  memory_max_bytes: number | null
+  oom_kills: number
+  pids: number
+  pids_max: number | null
 }
 
 export interface SessionEvent {
@@ -30,6 +134,11 @@ export interface SessionEvent {
   content: string
   timestamp: string
   metadata: string | null
+  session_id: string | null
+  transcript_path: string | null
+  tool_name: string | null
+  tool_input: string | null
+  tool_response: string | null
 }
 
 export interface WorkspaceStats {
@@ -40,10 +149,152 @@ export interface WorkspaceStats {
   last_active: string | null
 }
 
+export type PolicyDecision = 'allow' | 'deny' | 'ask' | 'queue'
+
+export interface PolicyRule {
+  id: string
+  enabled: boolean
+  workspace: string | null
+  tool_name: string | null
+  command_regex: string | null
+  path_globs: string[]
+  cwd_glob: string | null
+  outside_cwd: boolean
+  decision: PolicyDecision
+  reason: string | null
+}
+
+export interface PolicyDecisionRecord {
+  id: number
+  timestamp: string
+  instance_id: string
+  session_id: string
+  cwd: string
+  tool_name: string
+  tool_input: string | null
+  rule_id: string
+  decision: string
+  reason: string
+}
+
+export interface PendingApproval {
+  id: string
+  created_at: string
+  expires_at: string
+  instance_id: string
+  session_id: string
+  cwd: string
+  tool_name: string
+  tool_input: unknown
+  rule_id: string
+  reason: string
+}
+
+export interface ApprovalConfig {
+  timeout_secs: number
+  default_decision: PolicyDecision
+}
+
+export interface TerminationConfig {
+  interrupt_grace_secs: number
+  term_grace_secs: number
+}
+
+export interface ResourceLimits {
+  cpu_percent: number | null
+  memory_max_mb: number | null
+  pids_max: number | null
+}
+
+export interface CgroupConfig {
+  enabled: boolean
+  scope: 'instance' | 'workspace'
+  limits: ResourceLimits
+  workspaces: (ResourceLimits & { path: string })[]
+}
+
+export type NotificationKind = 'task_completed' | 'input_needed' | 'alert_fired' | 'alert_resolved' | 'test'
+
+export type NotificationChannel = { id: string } & (
+  | { type: 'desktop' }
+  | { type: 'webhook'; url: string; headers: Record<string, string>; template: unknown }
+  | {
+      type: 'smtp'
+      host: string
+      port: number
+      security: 'starttls' | 'tls' | 'none'
+      username: string | null
+      password: string | null
+      from: string
+      to: string[]
+    }
+  | { type: 'command'; program: string; args: string[] }
+)
+
+export interface NotificationRoute {
+  events: NotificationKind[]
+  workspace: string | null
+  channels: string[]
+  ignore_quiet_hours: boolean
+}
+
+export interface NotificationConfig {
+  enabled: boolean
+  channels: NotificationChannel[]
+  routes: NotificationRoute[]
+  quiet_hours: { start: string; end: string } | null
+}
+
 export interface AppConfig {
   hook_enabled: boolean
   auto_start_monitor: boolean
   polling_interval_secs: number
+  hook_port: number
+  hook_port_fallbacks: number
+  policy_rules: PolicyRule[]
+  approval: ApprovalConfig
+  alert_rules: AlertRule[]
+  termination: TerminationConfig
+  cgroup: CgroupConfig
+  notifications: NotificationConfig
+}
+
+export interface HookServerStatus {
+  running: boolean
+  port: number | null
+  socket_path: string | null
+  pid: number
+  error: string | null
+}
+
+export type MonitorEventKind =
+  | 'instance_appeared'
+  | 'instance_exited'
+  | 'resource_sample'
+  | 'hook_event_received'
+  | 'orphan_detected'
+  | 'termination_progress'
+  | 'instance_status_changed'
+  | 'alert_fired'
+  | 'alert_resolved'
+  | 'hook_server_started'
+
+export type MonitorEvent =
+  | { kind: 'instance_appeared'; payload: ClaudeInstance }
+  | { kind: 'instance_exited'; payload: { instance_id: string; pid: number; cwd: string; exit_reason: string | null; runtime_secs: number | null } }
+  | { kind: 'resource_sample'; payload: InstanceResource }
+  | { kind: 'hook_event_received'; payload: SessionEvent & { cursor: number } }
+  | { kind: 'orphan_detected'; payload: OrphanProcess }
+  | { kind: 'termination_progress'; payload: TerminationProgress }
+  | { kind: 'instance_status_changed'; payload: { instance_id: string; pid: number; status: string } }
+  | { kind: 'alert_fired'; payload: Alert }
+  | { kind: 'alert_resolved'; payload: Alert }
+  | { kind: 'hook_server_started'; payload: HookServerStatus }
+
+export type MonitorEventEnvelope = MonitorEvent & {
+  version: number
+  seq: number
+  timestamp: string
 }
 
 export type ViewType = 'dashboard' | 'install' | 'hooks' | 'history'